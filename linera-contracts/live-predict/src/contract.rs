@@ -8,16 +8,25 @@
 
 #![cfg_attr(target_arch = "wasm32", no_main)]
 
+mod lmsr;
 mod state;
+#[cfg(test)]
+mod test_support;
+
+use std::cmp::Ordering;
 
 use linera_sdk::{
-    linera_base_types::WithContractAbi,
+    abis::fungible,
+    linera_base_types::{self, Account, AccountOwner, ChainId, WithContractAbi},
     views::{RootView, View},
     Contract, ContractRuntime,
 };
 use live_predict::{
-    Amount, Bet, LivePredictAbi, Market, MarketOption, MarketStatus, Message, Operation,
-    OperationResponse, Timestamp,
+    Amount, Backing, Bet, BetId, Currency, InstantiationArgument, LedgerAccount, LedgerEntry,
+    LedgerReason, LegStatus, LivePredictAbi, LivePredictParameters, Market, MarketCondition,
+    MarketId, MarketKind, MarketOption, MarketStatus, Message, OddsConfig, Operation,
+    OperationResponse, Parlay, ParlayLeg, PricingMode, Role, StakeLimits, Timestamp,
+    WinningOption, NATIVE_CURRENCY,
 };
 
 use self::state::LivePredictState;
@@ -36,8 +45,8 @@ impl WithContractAbi for LivePredictContract {
 
 impl Contract for LivePredictContract {
     type Message = Message;
-    type InstantiationArgument = u32; // Fee rate in basis points
-    type Parameters = ();
+    type InstantiationArgument = InstantiationArgument;
+    type Parameters = LivePredictParameters;
     type EventValue = ();
 
     async fn load(runtime: ContractRuntime<Self>) -> Self {
@@ -47,15 +56,22 @@ impl Contract for LivePredictContract {
         LivePredictContract { state, runtime }
     }
 
-    async fn instantiate(&mut self, fee_rate_bps: u32) {
+    async fn instantiate(&mut self, argument: InstantiationArgument) {
         // Validate fee rate (max 5%)
-        assert!(fee_rate_bps <= 500, "Fee rate cannot exceed 5%");
+        assert!(argument.fee_rate_bps <= 500, "Fee rate cannot exceed 5%");
+        if argument.backing == Backing::Fungible {
+            assert!(
+                !self.runtime.application_parameters().tokens.is_empty(),
+                "Fungible backing requires a token application"
+            );
+        }
         
-        self.state.fee_rate_bps.set(fee_rate_bps);
+        self.state.fee_rate_bps.set(argument.fee_rate_bps);
+        self.state.backing.set(argument.backing);
         self.state.next_market_id.set(1);
         self.state.next_bet_id.set(1);
-        self.state.total_volume.set(0);
-        self.state.protocol_fees.set(0);
+        self.state.next_parlay_id.set(1);
+        self.state.stake_limits.set(StakeLimits::default());
         self.state.active_markets.set(vec![]);
     }
 
@@ -67,13 +83,43 @@ impl Contract for LivePredictContract {
                 title,
                 options,
                 locks_at,
-            } => self.create_market(match_id, market_type, title, options, locks_at).await,
+                kind,
+                line,
+                bounds,
+                pricing,
+                liquidity,
+                condition,
+                limits,
+                odds_config,
+                seed,
+                currency,
+            } => {
+                self.create_market(
+                    match_id,
+                    market_type,
+                    title,
+                    options,
+                    locks_at,
+                    kind,
+                    line,
+                    bounds,
+                    pricing,
+                    liquidity,
+                    condition,
+                    limits,
+                    odds_config,
+                    seed,
+                    currency,
+                )
+                .await
+            }
 
             Operation::PlaceBet {
                 market_id,
                 option_id,
                 amount,
-            } => self.place_bet(market_id, option_id, amount).await,
+                min_odds,
+            } => self.place_bet(market_id, option_id, amount, min_odds).await,
 
             Operation::LockMarket { market_id } => self.lock_market(market_id).await,
 
@@ -82,39 +128,84 @@ impl Contract for LivePredictContract {
                 winning_option,
             } => self.resolve_market(market_id, winning_option).await,
 
+            Operation::ResolveWinners { market_id, winners } => {
+                self.resolve_winners(market_id, winners).await
+            }
+
+            Operation::ResolveScalar { market_id, value } => {
+                self.resolve_scalar(market_id, value).await
+            }
+
+            Operation::ResolveScore {
+                market_id,
+                score_a,
+                score_b,
+            } => self.resolve_score(market_id, score_a, score_b).await,
+
+            Operation::VoidOption {
+                market_id,
+                option_id,
+            } => self.void_option(market_id, option_id).await,
+
             Operation::CancelMarket { market_id } => self.cancel_market(market_id).await,
 
             Operation::ClaimWinnings { bet_id } => self.claim_winnings(bet_id).await,
 
-            Operation::Deposit { amount } => self.deposit(amount).await,
+            Operation::CashOut { bet_id, min_value } => self.cash_out(bet_id, min_value).await,
+
+            Operation::PartialCashOut {
+                bet_id,
+                fraction_bps,
+                min_value,
+            } => self.partial_cash_out(bet_id, fraction_bps, min_value).await,
+
+            Operation::PlaceParlay { legs, amount } => self.place_parlay(legs, amount).await,
+
+            Operation::SettleParlay { parlay_id } => self.settle_parlay(parlay_id).await,
 
-            Operation::Withdraw { amount } => self.withdraw(amount).await,
+            Operation::SuspendMarket { market_id } => self.suspend_market(market_id).await,
+
+            Operation::ResumeMarket { market_id } => self.resume_market(market_id).await,
+
+            Operation::SetOdds { market_id, odds } => self.set_odds(market_id, odds).await,
+
+            Operation::FundHouse { currency, amount } => self.fund_house(currency, amount).await,
+
+            Operation::WithdrawHouse { currency, amount } => {
+                self.withdraw_house(currency, amount).await
+            }
+
+            Operation::SetStakeLimits { limits } => self.set_stake_limits(limits).await,
+
+            Operation::GrantRole { owner, role } => self.grant_role(owner, role).await,
+
+            Operation::RevokeRole { owner, role } => self.revoke_role(owner, role).await,
+
+            Operation::WithdrawFees { currency, amount } => {
+                self.withdraw_fees(currency, amount).await
+            }
+
+            Operation::Deposit { currency, amount } => self.deposit(currency, amount).await,
+
+            Operation::Withdraw { currency, amount } => self.withdraw(currency, amount).await,
         }
     }
 
     async fn execute_message(&mut self, message: Message) {
+        // Only the chain that created the application is trusted to publish market state.
+        if self.message_origin() != self.runtime.application_creator_chain_id() {
+            return;
+        }
+
         match message {
             Message::SyncMarket { market } => {
                 // Store synced market from another chain
+                let market_id = market.id;
                 self.state
                     .markets
-                    .insert(&market.id, market)
+                    .insert(&market_id, *market)
                     .expect("Failed to sync market");
             }
-            Message::MarketResolved {
-                market_id,
-                winning_option,
-            } => {
-                // Update local market state based on cross-chain resolution
-                if let Some(mut market) = self.state.get_market(market_id).await {
-                    market.status = MarketStatus::Resolved;
-                    market.winning_option = Some(winning_option);
-                    self.state
-                        .markets
-                        .insert(&market_id, market)
-                        .expect("Failed to update resolved market");
-                }
-            }
         }
     }
 
@@ -124,21 +215,227 @@ impl Contract for LivePredictContract {
 }
 
 impl LivePredictContract {
-    /// Get current timestamp (simulated for now).
-    fn current_time(&self) -> Timestamp {
-        // In production, this would use the block timestamp
-        std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_millis() as Timestamp
+    /// Get the current block timestamp.
+    fn current_time(&mut self) -> Timestamp {
+        self.runtime.system_time()
+    }
+
+    /// Get the chain that sent the message currently being executed.
+    fn message_origin(&mut self) -> ChainId {
+        self.runtime
+            .message_id()
+            .expect("Message must have an ID")
+            .chain_id
+    }
+
+    /// Check whether an owner is one of the application admins.
+    fn is_admin(&mut self, owner: &AccountOwner) -> bool {
+        self.runtime.application_parameters().admins.contains(owner)
+    }
+
+    /// Check that balances can be held in a currency.
+    fn check_currency(&mut self, currency: &Currency) -> Result<(), OperationResponse> {
+        let supported = match *self.state.backing.get() {
+            Backing::Fungible => self
+                .runtime
+                .application_parameters()
+                .tokens
+                .contains_key(currency),
+            Backing::Native => currency == NATIVE_CURRENCY,
+        };
+        if !supported {
+            return Err(OperationResponse::Error {
+                message: format!("Unsupported currency {currency}"),
+            });
+        }
+        Ok(())
+    }
+
+    /// Transfer the tokens backing balances in a currency between two accounts on this
+    /// chain.
+    fn transfer_tokens(
+        &mut self,
+        currency: &Currency,
+        source: AccountOwner,
+        target: AccountOwner,
+        amount: Amount,
+    ) {
+        let chain_id = self.runtime.chain_id();
+        let amount = linera_base_types::Amount::from_micros(amount);
+        match *self.state.backing.get() {
+            Backing::Fungible => {
+                let token = *self
+                    .runtime
+                    .application_parameters()
+                    .tokens
+                    .get(currency)
+                    .expect("Unsupported currency");
+                let transfer = fungible::Operation::Transfer {
+                    owner: source,
+                    amount,
+                    target_account: fungible::Account {
+                        chain_id,
+                        owner: target,
+                    },
+                };
+                self.runtime.call_application(true, token, &transfer);
+            }
+            Backing::Native => {
+                let destination = Account {
+                    chain_id,
+                    owner: target,
+                };
+                self.runtime.transfer(source, destination, amount);
+            }
+        }
+    }
+
+    /// Move funds from one ledger account to another, recording both sides in the
+    /// ledger. Owner balances are updated by the posting, and debited balances are
+    /// expected to have been checked.
+    // Each argument is a field of the entry, so they read best spelled out
+    #[allow(clippy::too_many_arguments)]
+    async fn post(
+        &mut self,
+        debit: LedgerAccount,
+        credit: LedgerAccount,
+        currency: &Currency,
+        amount: Amount,
+        reason: LedgerReason,
+        market_id: Option<MarketId>,
+        bet_id: Option<BetId>,
+    ) {
+        let entry = LedgerEntry {
+            debit,
+            credit,
+            currency: currency.clone(),
+            amount,
+            reason,
+            market_id,
+            bet_id,
+            timestamp: self.current_time(),
+        };
+        self.state.post_entry(entry).await;
+    }
+
+    /// Record how much house liquidity a market or parlay operation reserved or
+    /// returned, given the liquidity before it, as a movement between the house and
+    /// the market or parlay.
+    async fn post_house_change(
+        &mut self,
+        account: LedgerAccount,
+        market_id: Option<MarketId>,
+        currency: &Currency,
+        before: Amount,
+    ) {
+        let after = self.state.get_house_liquidity(currency).await;
+        if after > before {
+            self.post(
+                account,
+                LedgerAccount::House,
+                currency,
+                after - before,
+                LedgerReason::HouseReturn,
+                market_id,
+                None,
+            )
+            .await;
+        } else {
+            self.post(
+                LedgerAccount::House,
+                account,
+                currency,
+                before - after,
+                LedgerReason::HouseReserve,
+                market_id,
+                None,
+            )
+            .await;
+        }
+    }
+
+    /// Move a protocol fee from a market or parlay into the protocol fees.
+    async fn take_fee(
+        &mut self,
+        account: LedgerAccount,
+        market_id: Option<MarketId>,
+        currency: &Currency,
+        fee: Amount,
+        bet_id: Option<BetId>,
+    ) {
+        self.state.add_protocol_fees(currency, fee).await;
+        self.post(
+            account,
+            LedgerAccount::Protocol,
+            currency,
+            fee,
+            LedgerReason::Fee,
+            market_id,
+            bet_id,
+        )
+        .await;
+    }
+
+    /// Check whether an owner holds any of the given roles.
+    async fn has_any_role(&self, owner: &AccountOwner, roles: &[Role]) -> bool {
+        let granted = self.state.get_roles(owner).await;
+        roles.iter().any(|role| granted.contains(role))
+    }
+
+    /// Get the authenticated signer of the current operation.
+    fn signer(&mut self) -> Result<AccountOwner, OperationResponse> {
+        self.runtime
+            .authenticated_signer()
+            .ok_or_else(|| OperationResponse::Unauthorized {
+                message: "Operation must be signed".into(),
+            })
+    }
+
+    /// Check that the signer is an admin.
+    fn authorize_admin(&mut self) -> Result<AccountOwner, OperationResponse> {
+        let signer = self.signer()?;
+        if !self.is_admin(&signer) {
+            return Err(OperationResponse::Unauthorized {
+                message: "Only admins can perform this operation".into(),
+            });
+        }
+        Ok(signer)
+    }
+
+    /// Check that the signer is an admin or holds the given role.
+    async fn authorize_role(&mut self, role: Role) -> Result<AccountOwner, OperationResponse> {
+        let signer = self.signer()?;
+        if !self.is_admin(&signer) && !self.state.has_role(&signer, role).await {
+            return Err(OperationResponse::Unauthorized {
+                message: format!("Operation requires the {role:?} role"),
+            });
+        }
+        Ok(signer)
     }
 
-    /// Get the caller's chain ID as owner identifier.
-    fn caller_id(&self) -> String {
-        format!("{:?}", self.runtime.chain_id())
+    /// Check that the signer may manage a market's lifecycle: an admin, a holder of
+    /// one of `roles`, or the market's creator while they remain a market creator.
+    async fn authorize_market_operator(
+        &mut self,
+        market: &Market,
+        roles: &[Role],
+    ) -> Result<(), OperationResponse> {
+        let signer = self.signer()?;
+        if self.is_admin(&signer) || self.has_any_role(&signer, roles).await {
+            return Ok(());
+        }
+
+        if signer == market.creator && self.state.has_role(&signer, Role::MarketCreator).await {
+            return Ok(());
+        }
+
+        Err(OperationResponse::Unauthorized {
+            message: "Not allowed to manage this market".into(),
+        })
     }
 
     /// Create a new betting market.
+    #[allow(clippy::too_many_arguments)]
     async fn create_market(
         &mut self,
         match_id: String,
@@ -146,7 +443,63 @@ impl LivePredictContract {
         title: String,
         options: Vec<String>,
         locks_at: Timestamp,
+        kind: MarketKind,
+        line: Option<i64>,
+        bounds: Vec<i64>,
+        pricing: PricingMode,
+        liquidity: Amount,
+        condition: Option<(u64, u8)>,
+        limits: StakeLimits,
+        odds_config: Option<OddsConfig>,
+        seed: Vec<Amount>,
+        currency: Currency,
     ) -> OperationResponse {
+        let creator = match self.authorize_role(Role::MarketCreator).await {
+            Ok(owner) => owner,
+            Err(response) => return response,
+        };
+
+        if let Err(response) = self.check_currency(&currency) {
+            return response;
+        }
+
+        // Conditional markets need a parent outcome that can still happen
+        if let Some((parent_id, option_id)) = condition {
+            let parent = match self.state.get_market(parent_id).await {
+                Some(m) => m,
+                None => {
+                    return OperationResponse::Error {
+                        message: "Parent market not found".into(),
+                    }
+                }
+            };
+
+            match LivePredictState::condition_status(&parent, option_id) {
+                Some(LegStatus::Pending) => {}
+                Some(_) => {
+                    return OperationResponse::Error {
+                        message: "Parent market outcome already settled".into(),
+                    }
+                }
+                None => {
+                    return OperationResponse::Error {
+                        message: "Invalid parent option".into(),
+                    }
+                }
+            }
+        }
+
+        let teams = match kind {
+            MarketKind::Handicap => options.clone(),
+            _ => Vec::new(),
+        };
+
+        // Generate the options of numeric markets from their line or bounds
+        let options = match market_options(kind, line, &bounds, options) {
+            Ok(options) => options,
+            Err(response) => return response,
+        };
+
         // Validate inputs
         if options.len() < 2 || options.len() > 10 {
             return OperationResponse::Error {
@@ -160,7 +513,128 @@ impl LivePredictContract {
             };
         }
 
+        if !valid_limits(&limits) {
+            return OperationResponse::Error {
+                message: "Stake limits must be greater than 0".into(),
+            };
+        }
+
+        // LMSR prices come from the market maker and ignore odds bounds
+        if let Some(config) = odds_config {
+            if pricing == PricingMode::Lmsr {
+                return OperationResponse::Error {
+                    message: "Odds configuration doesn't apply to LMSR markets".into(),
+                };
+            }
+
+            if config.floor < 1000
+                || config.default_odds < config.floor
+                || config.cap < config.default_odds
+            {
+                return OperationResponse::Error {
+                    message: "Odds must satisfy 1x <= floor <= default <= cap".into(),
+                };
+            }
+
+            if pricing == PricingMode::FixedOdds && config.default_odds <= 1000 {
+                return OperationResponse::Error {
+                    message: "Fixed odds must be above 1x".into(),
+                };
+            }
+        }
+
+        // Seed liquidity comes from the creator's balance
+        let seed_total =
+            seed.iter().try_fold(0, |total: Amount, &amount| total.checked_add(amount));
+        let Some(seed_total) = seed_total else {
+            return OperationResponse::Error {
+                message: "Seed amount too large".into(),
+            };
+        };
+        if !seed.is_empty() {
+            if pricing != PricingMode::Parimutuel {
+                return OperationResponse::Error {
+                    message: "Seed liquidity only applies to parimutuel markets".into(),
+                };
+            }
+
+            if seed.len() != options.len() {
+                return OperationResponse::Error {
+                    message: "Seed must have one amount per option".into(),
+                };
+            }
+
+            // The seed opens the market's pools, so it counts toward the pool limit
+            let max_pool = limits.max_pool.or(self.state.stake_limits.get().max_pool);
+            if let Some(limit) = max_pool.filter(|&limit| seed_total > limit) {
+                return OperationResponse::StakeLimitExceeded {
+                    limit,
+                    message: "Seed exceeds the maximum market pool".into(),
+                };
+            }
+
+            if self.state.get_balance(&creator, &currency).await < seed_total {
+                return OperationResponse::Error {
+                    message: "Insufficient balance".into(),
+                };
+            }
+        }
+
+        // Fixed-odds options open at the configured default odds, if any
+        let opening_odds = match (pricing, odds_config) {
+            (PricingMode::FixedOdds, Some(config)) => config.default_odds,
+            _ => 0,
+        };
+
+        // LMSR markets reserve the market maker's worst-case loss up front
+        let house_reserve = match pricing {
+            PricingMode::Lmsr => {
+                let Some(max_loss) = lmsr::max_loss(liquidity, options.len()) else {
+                    return OperationResponse::Error {
+                        message: "Invalid liquidity parameter".into(),
+                    };
+                };
+                let house_liquidity = self.state.get_house_liquidity(&currency).await;
+                if liquidity == 0 || max_loss > house_liquidity {
+                    return OperationResponse::Error {
+                        message: "Insufficient house liquidity for market".into(),
+                    };
+                }
+                self.state.set_house_liquidity(&currency, house_liquidity - max_loss);
+                max_loss
+            }
+            _ if liquidity != 0 => {
+                return OperationResponse::Error {
+                    message: "Liquidity parameter only applies to LMSR markets".into(),
+                }
+            }
+            _ => 0,
+        };
+
         let market_id = self.state.allocate_market_id().await;
+        let escrow = LedgerAccount::Market(market_id);
+        self.post(
+            LedgerAccount::House,
+            escrow,
+            &currency,
+            house_reserve,
+            LedgerReason::HouseReserve,
+            Some(market_id),
+            None,
+        )
+        .await;
+        self.post(
+            LedgerAccount::Owner(creator),
+            escrow,
+            &currency,
+            seed_total,
+            LedgerReason::Seed,
+            Some(market_id),
+            None,
+        )
+        .await;
+        // The seed stays at stake until it's returned, like the stakes of bets
+        self.state.lock_stake(&creator, &currency, seed_total).await;
         
         let market_options: Vec<MarketOption> = options
             .into_iter()
@@ -168,7 +642,10 @@ impl LivePredictContract {
             .map(|(i, label)| MarketOption {
                 id: i as u8,
                 label,
-                pool: 0,
+                pool: seed.get(i).copied().unwrap_or(0),
+                odds: opening_odds,
+                liability: 0,
+                voided: false,
             })
             .collect();
 
@@ -177,11 +654,31 @@ impl LivePredictContract {
             match_id,
             market_type,
             title,
+            kind,
+            line,
+            teams,
+            bounds,
+            creator,
+            currency,
+            odds_config: odds_config.unwrap_or_default(),
+            limits,
+            condition: condition.map(|(market_id, option_id)| MarketCondition {
+                market_id,
+                option_id,
+            }),
+            pricing,
+            liquidity,
             options: market_options,
             status: MarketStatus::Open,
             created_at: self.current_time(),
             locks_at,
-            winning_option: None,
+            winners: Vec::new(),
+            result: None,
+            payout_pool: 0,
+            house_reserve,
+            retained: 0,
+            seed,
+            seed_payout: None,
         };
 
         self.state
@@ -190,6 +687,9 @@ impl LivePredictContract {
             .expect("Failed to create market");
         
         self.state.add_active_market(market_id).await;
+        if let Some((parent_id, _)) = condition {
+            self.state.add_dependent(parent_id, market_id).await;
+        }
 
         OperationResponse::MarketCreated { market_id }
     }
@@ -200,8 +700,12 @@ impl LivePredictContract {
         market_id: u64,
         option_id: u8,
         amount: Amount,
+        min_odds: Option<u32>,
     ) -> OperationResponse {
-        let owner = self.caller_id();
+        let owner = match self.signer() {
+            Ok(owner) => owner,
+            Err(response) => return response,
+        };
 
         // Validate amount
         if amount == 0 {
@@ -210,14 +714,6 @@ impl LivePredictContract {
             };
         }
 
-        // Check user balance
-        let balance = self.state.get_balance(&owner).await;
-        if balance < amount {
-            return OperationResponse::Error {
-                message: "Insufficient balance".into(),
-            };
-        }
-
         // Get and validate market
         let mut market = match self.state.get_market(market_id).await {
             Some(m) => m,
@@ -228,6 +724,15 @@ impl LivePredictContract {
             }
         };
 
+        // Check user balance in the market's currency
+        let currency = market.currency.clone();
+        let balance = self.state.get_balance(&owner, &currency).await;
+        if balance < amount {
+            return OperationResponse::Error {
+                message: "Insufficient balance".into(),
+            };
+        }
+
         if market.status != MarketStatus::Open {
             return OperationResponse::Error {
                 message: "Market is not open for betting".into(),
@@ -241,36 +746,130 @@ impl LivePredictContract {
         }
 
         // Validate option
-        let option = match market.options.get_mut(option_id as usize) {
-            Some(o) => o,
-            None => {
-                return OperationResponse::Error {
-                    message: "Invalid option".into(),
+        if option_id as usize >= market.options.len() {
+            return OperationResponse::Error {
+                message: "Invalid option".into(),
+            };
+        }
+
+        if market.options[option_id as usize].voided {
+            return OperationResponse::Error {
+                message: "Option has been voided".into(),
+            };
+        }
+
+        // Enforce the market's stake limits
+        let limits = self.state.market_limits(&market);
+        if let Some(limit) = limits.max_stake.filter(|&limit| amount > limit) {
+            return OperationResponse::StakeLimitExceeded {
+                limit,
+                message: "Stake exceeds the maximum single stake".into(),
+            };
+        }
+
+        let owner_stake = self.state.get_owner_stake(market_id, &owner).await;
+        if let Some(limit) = limits.max_owner_stake.filter(|&limit| owner_stake + amount > limit) {
+            return OperationResponse::StakeLimitExceeded {
+                limit,
+                message: "Stake exceeds the maximum total stake per owner on this market".into(),
+            };
+        }
+
+        let total_pool: Amount = market.options.iter().map(|o| o.pool).sum();
+        if let Some(limit) = limits.max_pool.filter(|&limit| total_pool + amount > limit) {
+            return OperationResponse::StakeLimitExceeded {
+                limit,
+                message: "Stake exceeds the maximum market pool".into(),
+            };
+        }
+
+        // Price the bet
+        let (odds, shares) = match market.pricing {
+            PricingMode::Parimutuel => {
+                let total_pool: Amount = market.options.iter().map(|o| o.pool).sum();
+                let option_pool = market.options[option_id as usize].pool;
+                let odds = LivePredictState::calculate_odds(
+                    &market.odds_config,
+                    total_pool + amount,
+                    option_pool + amount,
+                );
+                (odds, 0)
+            }
+            PricingMode::FixedOdds => {
+                let odds = market.options[option_id as usize].odds;
+                if odds == 0 {
+                    return OperationResponse::Error {
+                        message: "Odds not set for this option".into(),
+                    };
                 }
+                (odds, LivePredictState::calculate_fixed_payout(amount, odds))
+            }
+            PricingMode::Lmsr => {
+                let shares = match LivePredictState::lmsr_book(&market, option_id).and_then(
+                    |(outstanding, position)| {
+                        lmsr::shares_for_cost(&outstanding, market.liquidity, position, amount)
+                    },
+                ) {
+                    Some(shares) if shares > 0 => shares,
+                    _ => {
+                        return OperationResponse::Error {
+                            message: "Bet size out of range for market liquidity".into(),
+                        }
+                    }
+                };
+                // Effective odds of the shares bought
+                let odds = u32::try_from(shares * 1000 / amount).unwrap_or(u32::MAX);
+                (odds, shares)
             }
         };
 
-        // Calculate total pool and current odds
-        let total_pool: Amount = market.options.iter().map(|o| o.pool).sum();
-        let odds = LivePredictState::calculate_odds(total_pool + amount, option.pool + amount);
+        // Reject the bet if the odds moved below the caller's limit
+        if let Some(min_odds) = min_odds {
+            if odds < min_odds {
+                return OperationResponse::SlippageExceeded { odds, min_odds };
+            }
+        }
 
-        // Deduct from balance
-        self.state.set_balance(&owner, balance - amount).await;
+        // Update the option pool, reserving house liquidity for house-backed markets
+        let house_before = self.state.get_house_liquidity(&currency).await;
+        market.options[option_id as usize].pool += amount;
+        if market.pricing != PricingMode::Parimutuel
+            && !self.state.reserve_liability(&mut market, option_id, shares).await
+        {
+            return OperationResponse::Error {
+                message: "Bet exceeds house liquidity".into(),
+            };
+        }
 
-        // Update option pool
-        option.pool += amount;
+        // Move the stake from the available balance into the locked balance
+        let bet_id = self.state.allocate_bet_id().await;
+        let escrow = LedgerAccount::Market(market_id);
+        self.post(
+            LedgerAccount::Owner(owner),
+            escrow,
+            &currency,
+            amount,
+            LedgerReason::Stake,
+            Some(market_id),
+            Some(bet_id),
+        )
+        .await;
+        self.post_house_change(escrow, Some(market_id), &currency, house_before).await;
+        self.state.lock_stake(&owner, &currency, amount).await;
 
         // Create bet
-        let bet_id = self.state.allocate_bet_id().await;
         let bet = Bet {
             id: bet_id,
-            owner: owner.clone(),
+            owner,
             market_id,
             option_id,
             amount,
             odds,
+            shares,
             placed_at: self.current_time(),
             settled: false,
+            cashed_out: false,
+            split_from: None,
             payout: None,
         };
 
@@ -279,10 +878,10 @@ impl LivePredictContract {
         self.state.markets.insert(&market_id, market).expect("Failed to update market");
         self.state.add_user_bet(&owner, bet_id).await;
         self.state.add_market_bet(market_id, bet_id).await;
+        self.state.add_owner_stake(market_id, &owner, amount).await;
 
         // Update total volume
-        let new_volume = self.state.total_volume.get() + amount;
-        self.state.total_volume.set(new_volume);
+        self.state.add_total_volume(&currency, amount).await;
 
         OperationResponse::BetPlaced { bet_id, odds }
     }
@@ -298,7 +897,14 @@ impl LivePredictContract {
             }
         };
 
-        if market.status != MarketStatus::Open {
+        if let Err(response) = self
+            .authorize_market_operator(&market, &[Role::Resolver, Role::RiskManager])
+            .await
+        {
+            return response;
+        }
+
+        if !matches!(market.status, MarketStatus::Open | MarketStatus::Suspended) {
             return OperationResponse::Error {
                 message: "Market is not open".into(),
             };
@@ -311,83 +917,844 @@ impl LivePredictContract {
         OperationResponse::MarketLocked { market_id }
     }
 
-    /// Resolve a market with the winning option.
-    async fn resolve_market(&mut self, market_id: u64, winning_option: u8) -> OperationResponse {
-        let mut market = match self.state.get_market(market_id).await {
-            Some(m) => m,
-            None => {
-                return OperationResponse::Error {
-                    message: "Market not found".into(),
-                }
-            }
-        };
+    /// Load a market the signer may resolve, checking it hasn't been settled yet.
+    async fn resolvable_market(&mut self, market_id: u64) -> Result<Market, OperationResponse> {
+        let market = self
+            .state
+            .get_market(market_id)
+            .await
+            .ok_or_else(|| OperationResponse::Error {
+                message: "Market not found".into(),
+            })?;
 
-        if market.status == MarketStatus::Resolved {
-            return OperationResponse::Error {
+        self.authorize_market_operator(&market, &[Role::Resolver]).await?;
+
+        if matches!(market.status, MarketStatus::Resolved | MarketStatus::Pushed) {
+            return Err(OperationResponse::Error {
                 message: "Market already resolved".into(),
-            };
+            });
         }
 
-        if winning_option as usize >= market.options.len() {
-            return OperationResponse::Error {
-                message: "Invalid winning option".into(),
-            };
+        if market.status == MarketStatus::Cancelled {
+            return Err(OperationResponse::Error {
+                message: "Cannot resolve cancelled market".into(),
+            });
+        }
+
+        if let Some(condition) = market.condition {
+            let parent = self.state.get_market(condition.market_id).await;
+            let status =
+                parent.and_then(|m| LivePredictState::condition_status(&m, condition.option_id));
+            if status != Some(LegStatus::Won) {
+                return Err(OperationResponse::Error {
+                    message: "Market condition not yet met".into(),
+                });
+            }
         }
 
+        Ok(market)
+    }
+
+    /// Settle a market's pools and mark it resolved, expecting its winners to be set.
+    async fn settle_market(&mut self, mut market: Market) {
+        // Winners share the final total pool minus the protocol fee
+        let fee_rate = *self.state.fee_rate_bps.get();
+        let fee = LivePredictState::settle_pools(&mut market, fee_rate);
+        let escrow = LedgerAccount::Market(market.id);
+        self.take_fee(escrow, Some(market.id), &market.currency, fee, None).await;
+
+        if market.pricing != PricingMode::Parimutuel {
+            let house_before = self.state.get_house_liquidity(&market.currency).await;
+            self.state.settle_house(&mut market).await;
+            self.post_house_change(escrow, Some(market.id), &market.currency, house_before).await;
+        }
+
+        let seed_payout = LivePredictState::seed_payout(&market);
+        self.return_seed(&mut market, seed_payout).await;
+
+        // Losing bets can't pay anything, so their stakes stop being locked now
+        self.settle_losing_bets(&market).await;
+
+        let market_id = market.id;
         market.status = MarketStatus::Resolved;
-        market.winning_option = Some(winning_option);
         self.state.markets.insert(&market_id, market).expect("Failed to resolve market");
         self.state.remove_active_market(market_id).await;
+        self.cancel_dependents(market_id).await;
+    }
 
-        OperationResponse::MarketResolved {
-            market_id,
-            winning_option,
+    /// Settle the unsettled bets on the losing options of a resolved market, unlocking
+    /// their stakes. Winning and refunded bets stay open until claimed.
+    async fn settle_losing_bets(&mut self, market: &Market) {
+        let bet_ids = self.state.market_bets.get(&market.id).await.ok().flatten();
+        for bet_id in bet_ids.unwrap_or_default() {
+            if let Some(mut bet) = self.state.get_bet(bet_id).await {
+                if !bet.settled && LivePredictState::is_losing_option(market, bet.option_id) {
+                    self.state.unlock_stake(&bet.owner, &market.currency, bet.amount).await;
+                    bet.settled = true;
+                    bet.payout = Some(0);
+                    self.state.bets.insert(&bet_id, bet).expect("Failed to settle bet");
+                }
+            }
         }
     }
 
-    /// Cancel a market and refund all bets.
-    async fn cancel_market(&mut self, market_id: u64) -> OperationResponse {
-        let mut market = match self.state.get_market(market_id).await {
-            Some(m) => m,
-            None => {
-                return OperationResponse::Error {
-                    message: "Market not found".into(),
+    /// Cancel and refund the markets conditional on a market whose condition can no
+    /// longer be met, along with their own dependents.
+    async fn cancel_dependents(&mut self, market_id: u64) {
+        let Some(parent) = self.state.get_market(market_id).await else {
+            return;
+        };
+
+        let mut cancelled = Vec::new();
+        for dependent_id in self.state.get_dependents(market_id).await {
+            if let Some(dependent) = self.state.get_market(dependent_id).await {
+                let option_id = dependent.condition.map_or(0, |c| c.option_id);
+                if matches!(
+                    LivePredictState::condition_status(&parent, option_id),
+                    Some(LegStatus::Lost | LegStatus::Void)
+                ) {
+                    cancelled.push(dependent);
                 }
             }
-        };
+        }
 
-        if market.status == MarketStatus::Resolved {
-            return OperationResponse::Error {
-                message: "Cannot cancel resolved market".into(),
-            };
+        while let Some(mut market) = cancelled.pop() {
+            if matches!(
+                market.status,
+                MarketStatus::Resolved | MarketStatus::Cancelled | MarketStatus::Pushed
+            ) {
+                continue;
+            }
+
+            self.refund_market(&mut market).await;
+            market.status = MarketStatus::Cancelled;
+            let dependent_id = market.id;
+            self.state.markets.insert(&dependent_id, market).expect("Failed to cancel market");
+            self.state.remove_active_market(dependent_id).await;
+
+            // Markets conditional on a cancelled market can't be met either
+            for id in self.state.get_dependents(dependent_id).await {
+                if let Some(dependent) = self.state.get_market(id).await {
+                    cancelled.push(dependent);
+                }
+            }
         }
+    }
 
-        // Refund all bets for this market
-        if let Ok(Some(bet_ids)) = self.state.market_bets.get(&market_id).await {
+    /// Refund every unsettled bet on a market and release its reserves.
+    async fn refund_market(&mut self, market: &mut Market) {
+        if let Ok(Some(bet_ids)) = self.state.market_bets.get(&market.id).await {
             for bet_id in bet_ids {
                 if let Some(mut bet) = self.state.get_bet(bet_id).await {
                     if !bet.settled {
-                        // Refund the bet amount
-                        let balance = self.state.get_balance(&bet.owner).await;
-                        self.state.set_balance(&bet.owner, balance + bet.amount).await;
-                        bet.settled = true;
-                        bet.payout = Some(bet.amount); // Refund = original amount
+                        self.refund_bet(&market.currency, &mut bet).await;
                         self.state.bets.insert(&bet_id, bet).expect("Failed to refund bet");
                     }
                 }
             }
         }
 
-        market.status = MarketStatus::Cancelled;
-        self.state.markets.insert(&market_id, market).expect("Failed to cancel market");
-        self.state.remove_active_market(market_id).await;
+        let escrow = LedgerAccount::Market(market.id);
+        if market.pricing != PricingMode::Parimutuel {
+            let house_before = self.state.get_house_liquidity(&market.currency).await;
+            self.state.release_house_reserve(market).await;
+            self.post_house_change(escrow, Some(market.id), &market.currency, house_before).await;
+        }
 
-        OperationResponse::MarketCancelled { market_id }
+        let seed: Amount = market.seed.iter().sum();
+        self.return_seed(market, seed).await;
+
+        // Stakes withheld by cash-outs belong to nobody once the bets are refunded
+        self.take_fee(escrow, Some(market.id), &market.currency, market.retained, None).await;
+        market.retained = 0;
+    }
+
+    /// Refund an unsettled bet's stake to its owner and mark it settled.
+    async fn refund_bet(&mut self, currency: &Currency, bet: &mut Bet) {
+        self.post(
+            LedgerAccount::Market(bet.market_id),
+            LedgerAccount::Owner(bet.owner),
+            currency,
+            bet.amount,
+            LedgerReason::Refund,
+            Some(bet.market_id),
+            Some(bet.id),
+        )
+        .await;
+        self.state.unlock_stake(&bet.owner, currency, bet.amount).await;
+        self.state.remove_owner_stake(bet.market_id, &bet.owner, bet.amount).await;
+        bet.settled = true;
+        bet.payout = Some(bet.amount); // Refund = original amount
+    }
+
+    /// Credit the creator of a seeded market with what its seed returned on settlement.
+    /// The seed is only returned once, whatever settles the market.
+    async fn return_seed(&mut self, market: &mut Market, amount: Amount) {
+        if market.seed.is_empty() || market.seed_payout.is_some() {
+            return;
+        }
+        self.post(
+            LedgerAccount::Market(market.id),
+            LedgerAccount::Owner(market.creator),
+            &market.currency,
+            amount,
+            LedgerReason::SeedReturn,
+            Some(market.id),
+            None,
+        )
+        .await;
+        let seed: Amount = market.seed.iter().sum();
+        self.state.unlock_stake(&market.creator, &market.currency, seed).await;
+        market.seed_payout = Some(amount);
+    }
+
+    /// Resolve a categorical market with the winning option.
+    async fn resolve_market(&mut self, market_id: u64, winning_option: u8) -> OperationResponse {
+        let mut market = match self.resolvable_market(market_id).await {
+            Ok(market) => market,
+            Err(response) => return response,
+        };
+
+        // Numeric markets settle from their result, including pushes
+        if market.kind != MarketKind::Categorical {
+            return OperationResponse::Error {
+                message: "Numeric markets are resolved by value".into(),
+            };
+        }
+
+        if winning_option as usize >= market.options.len() {
+            return OperationResponse::Error {
+                message: "Invalid winning option".into(),
+            };
+        }
+
+        if market.options[winning_option as usize].voided {
+            return OperationResponse::Error {
+                message: "Cannot resolve to a voided option".into(),
+            };
+        }
+
+        market.winners = vec![WinningOption {
+            option_id: winning_option,
+            weight: 10000,
+        }];
+        self.settle_market(market).await;
+
+        OperationResponse::MarketResolved {
+            market_id,
+            winning_option,
+        }
+    }
+
+    /// Resolve a categorical market with several winning options. Each winner pays its
+    /// weight of a full win, so "top 2" markets have two full-weight winners and dead
+    /// heats split the weight between the tied options.
+    async fn resolve_winners(
+        &mut self,
+        market_id: u64,
+        winners: Vec<(u8, Option<u32>)>,
+    ) -> OperationResponse {
+        let mut market = match self.resolvable_market(market_id).await {
+            Ok(market) => market,
+            Err(response) => return response,
+        };
+
+        // Numeric markets settle from their result, including pushes
+        if market.kind != MarketKind::Categorical {
+            return OperationResponse::Error {
+                message: "Numeric markets are resolved by value".into(),
+            };
+        }
+
+        if winners.is_empty() {
+            return OperationResponse::Error {
+                message: "At least one winning option is required".into(),
+            };
+        }
+
+        let mut winning_options: Vec<WinningOption> = Vec::new();
+        for (option_id, weight) in winners {
+            if option_id as usize >= market.options.len()
+                || winning_options.iter().any(|winner| winner.option_id == option_id)
+            {
+                return OperationResponse::Error {
+                    message: "Invalid winning option".into(),
+                };
+            }
+
+            if market.options[option_id as usize].voided {
+                return OperationResponse::Error {
+                    message: "Cannot resolve to a voided option".into(),
+                };
+            }
+
+            let weight = weight.unwrap_or(10000);
+            if weight == 0 || weight > 10000 {
+                return OperationResponse::Error {
+                    message: "Winner weight must be between 1 and 10000 basis points".into(),
+                };
+            }
+
+            winning_options.push(WinningOption { option_id, weight });
+        }
+
+        // Weights must add up to whole places, and the house only reserves liquidity
+        // for one winning place
+        let total_weight: u32 = winning_options.iter().map(|winner| winner.weight).sum();
+        if !total_weight.is_multiple_of(10000) {
+            return OperationResponse::Error {
+                message: "Winner weights must add up to a multiple of 10000 basis points".into(),
+            };
+        }
+        if market.pricing != PricingMode::Parimutuel && total_weight > 10000 {
+            return OperationResponse::Error {
+                message: "House-backed markets can only pay out one full win".into(),
+            };
+        }
+
+        market.winners = winning_options.clone();
+        self.settle_market(market).await;
+
+        OperationResponse::WinnersResolved {
+            market_id,
+            winners: winning_options,
+        }
+    }
+
+    /// Resolve an over/under, range or scalar market from its numeric result.
+    async fn resolve_scalar(&mut self, market_id: u64, value: i64) -> OperationResponse {
+        // Handicap margins are derived from the score, so they have a single scale
+        if let Some(market) = self.state.get_market(market_id).await {
+            if market.kind == MarketKind::Handicap {
+                return OperationResponse::Error {
+                    message: "Handicap markets are resolved by score".into(),
+                };
+            }
+        }
+        self.resolve_value(market_id, value).await
+    }
+
+    /// Resolve a market from its numeric result, team A's winning margin for
+    /// handicap markets.
+    async fn resolve_value(&mut self, market_id: u64, value: i64) -> OperationResponse {
+        let mut market = match self.resolvable_market(market_id).await {
+            Ok(market) => market,
+            Err(response) => return response,
+        };
+
+        let winning_option = match (market.kind, market.line) {
+            (MarketKind::OverUnder | MarketKind::Handicap, Some(line)) => {
+                // Team A covers a handicap if its margin plus the spread is positive
+                let threshold = match market.kind {
+                    MarketKind::Handicap => -line,
+                    _ => line,
+                };
+                match value.cmp(&threshold) {
+                    Ordering::Greater => 0,
+                    Ordering::Less => 1,
+                    Ordering::Equal => {
+                        // Push, every stake is refunded
+                        market.result = Some(value);
+                        self.refund_market(&mut market).await;
+                        market.status = MarketStatus::Pushed;
+                        self.state
+                            .markets
+                            .insert(&market_id, market)
+                            .expect("Failed to push market");
+                        self.state.remove_active_market(market_id).await;
+                        self.cancel_dependents(market_id).await;
+                        return OperationResponse::MarketPushed { market_id, value };
+                    }
+                }
+            }
+            // The winning bucket is the number of boundaries at or below the value
+            (MarketKind::Range, _) => {
+                market.bounds.iter().filter(|&&bound| value >= bound).count() as u8
+            }
+            (MarketKind::Scalar, _) => {
+                let (long_weight_bps, short_weight_bps) =
+                    LivePredictState::scalar_weights(&market, value);
+                market.result = Some(value);
+                market.winners = vec![
+                    WinningOption {
+                        option_id: 0,
+                        weight: long_weight_bps,
+                    },
+                    WinningOption {
+                        option_id: 1,
+                        weight: short_weight_bps,
+                    },
+                ];
+                self.settle_market(market).await;
+                return OperationResponse::ScalarResolved {
+                    market_id,
+                    value,
+                    long_weight_bps,
+                };
+            }
+            _ => {
+                return OperationResponse::Error {
+                    message: "Market is not resolved by value".into(),
+                }
+            }
+        };
+
+        if market.options[winning_option as usize].voided {
+            return OperationResponse::Error {
+                message: "Cannot resolve to a voided option".into(),
+            };
+        }
+
+        market.result = Some(value);
+        market.winners = vec![WinningOption {
+            option_id: winning_option,
+            weight: 10000,
+        }];
+        self.settle_market(market).await;
+
+        OperationResponse::MarketResolved {
+            market_id,
+            winning_option,
+        }
+    }
+
+    /// Void a single option, refunding every bet on it. The remaining options keep
+    /// trading, parimutuel odds being recalculated from the pools left.
+    async fn void_option(&mut self, market_id: u64, option_id: u8) -> OperationResponse {
+        let mut market = match self.state.get_market(market_id).await {
+            Some(m) => m,
+            None => {
+                return OperationResponse::Error {
+                    message: "Market not found".into(),
+                }
+            }
+        };
+
+        if let Err(response) = self
+            .authorize_market_operator(&market, &[Role::RiskManager])
+            .await
+        {
+            return response;
+        }
+
+        if !matches!(
+            market.status,
+            MarketStatus::Open | MarketStatus::Suspended | MarketStatus::Locked
+        ) {
+            return OperationResponse::Error {
+                message: "Market already settled".into(),
+            };
+        }
+
+        // A resolved value can land on any of their options, voided or not
+        if market.kind != MarketKind::Categorical {
+            return OperationResponse::Error {
+                message: "Numeric markets can't void options, cancel the market instead".into(),
+            };
+        }
+
+        match market.options.get(option_id as usize) {
+            Some(option) if option.voided => {
+                return OperationResponse::Error {
+                    message: "Option already voided".into(),
+                }
+            }
+            Some(_) => {}
+            None => {
+                return OperationResponse::Error {
+                    message: "Invalid option".into(),
+                }
+            }
+        }
+
+        if market.options.iter().filter(|o| !o.voided).count() <= 2 {
+            return OperationResponse::Error {
+                message: "Market needs at least two remaining options".into(),
+            };
+        }
+
+        // Remove the option's pool, re-reserving house liquidity for house-backed markets
+        let escrow = LedgerAccount::Market(market_id);
+        let house_before = self.state.get_house_liquidity(&market.currency).await;
+        if market.pricing == PricingMode::Parimutuel {
+            market.options[option_id as usize].pool = 0;
+        } else if !self.state.void_house_option(&mut market, option_id).await {
+            return OperationResponse::Error {
+                message: "Voiding the option exceeds house liquidity".into(),
+            };
+        }
+        self.post_house_change(escrow, Some(market_id), &market.currency, house_before).await;
+
+        // Refund all bets on the option, and the creator's seed on it
+        let mut refunded = 0;
+        if let Some(seed) = market.seed.get(option_id as usize).copied() {
+            self.post(
+                escrow,
+                LedgerAccount::Owner(market.creator),
+                &market.currency,
+                seed,
+                LedgerReason::SeedReturn,
+                Some(market_id),
+                None,
+            )
+            .await;
+            self.state.unlock_stake(&market.creator, &market.currency, seed).await;
+            market.seed[option_id as usize] = 0;
+            refunded += seed;
+        }
+        if let Ok(Some(bet_ids)) = self.state.market_bets.get(&market_id).await {
+            for bet_id in bet_ids {
+                if let Some(mut bet) = self.state.get_bet(bet_id).await {
+                    if bet.option_id == option_id && !bet.settled {
+                        self.refund_bet(&market.currency, &mut bet).await;
+                        refunded += bet.amount;
+                        self.state.bets.insert(&bet_id, bet).expect("Failed to refund bet");
+                    }
+                }
+            }
+        }
+
+        let option = &mut market.options[option_id as usize];
+        option.voided = true;
+        option.odds = 0;
+        self.state.markets.insert(&market_id, market).expect("Failed to void option");
+        self.cancel_dependents(market_id).await;
+
+        OperationResponse::OptionVoided {
+            market_id,
+            option_id,
+            refunded,
+        }
+    }
+
+    /// Resolve a handicap market from the final score, as team A's winning margin.
+    async fn resolve_score(
+        &mut self,
+        market_id: u64,
+        score_a: u32,
+        score_b: u32,
+    ) -> OperationResponse {
+        match self.state.get_market(market_id).await {
+            Some(market) if market.kind == MarketKind::Handicap => {}
+            Some(_) => {
+                return OperationResponse::Error {
+                    message: "Only handicap markets are resolved by score".into(),
+                }
+            }
+            None => {
+                return OperationResponse::Error {
+                    message: "Market not found".into(),
+                }
+            }
+        }
+
+        let margin = (score_a as i64 - score_b as i64) * 1000;
+        self.resolve_value(market_id, margin).await
+    }
+
+    /// Cancel a market and refund all bets.
+    async fn cancel_market(&mut self, market_id: u64) -> OperationResponse {
+        let mut market = match self.state.get_market(market_id).await {
+            Some(m) => m,
+            None => {
+                return OperationResponse::Error {
+                    message: "Market not found".into(),
+                }
+            }
+        };
+
+        if let Err(response) = self
+            .authorize_market_operator(&market, &[Role::RiskManager])
+            .await
+        {
+            return response;
+        }
+
+        if matches!(market.status, MarketStatus::Resolved | MarketStatus::Pushed) {
+            return OperationResponse::Error {
+                message: "Cannot cancel resolved market".into(),
+            };
+        }
+
+        if market.status == MarketStatus::Cancelled {
+            return OperationResponse::Error {
+                message: "Market already cancelled".into(),
+            };
+        }
+
+        self.refund_market(&mut market).await;
+
+        market.status = MarketStatus::Cancelled;
+        self.state.markets.insert(&market_id, market).expect("Failed to cancel market");
+        self.state.remove_active_market(market_id).await;
+        self.cancel_dependents(market_id).await;
+
+        OperationResponse::MarketCancelled { market_id }
+    }
+
+    /// Suspend betting on an open market.
+    async fn suspend_market(&mut self, market_id: u64) -> OperationResponse {
+        let mut market = match self.state.get_market(market_id).await {
+            Some(m) => m,
+            None => {
+                return OperationResponse::Error {
+                    message: "Market not found".into(),
+                }
+            }
+        };
+
+        if let Err(response) = self
+            .authorize_market_operator(&market, &[Role::RiskManager])
+            .await
+        {
+            return response;
+        }
+
+        if market.status != MarketStatus::Open {
+            return OperationResponse::Error {
+                message: "Market is not open".into(),
+            };
+        }
+
+        market.status = MarketStatus::Suspended;
+        self.state.markets.insert(&market_id, market).expect("Failed to suspend market");
+
+        OperationResponse::MarketSuspended { market_id }
+    }
+
+    /// Resume betting on a suspended market.
+    async fn resume_market(&mut self, market_id: u64) -> OperationResponse {
+        let mut market = match self.state.get_market(market_id).await {
+            Some(m) => m,
+            None => {
+                return OperationResponse::Error {
+                    message: "Market not found".into(),
+                }
+            }
+        };
+
+        if let Err(response) = self
+            .authorize_market_operator(&market, &[Role::RiskManager])
+            .await
+        {
+            return response;
+        }
+
+        if market.status != MarketStatus::Suspended {
+            return OperationResponse::Error {
+                message: "Market is not suspended".into(),
+            };
+        }
+
+        market.status = MarketStatus::Open;
+        self.state.markets.insert(&market_id, market).expect("Failed to resume market");
+
+        OperationResponse::MarketResumed { market_id }
+    }
+
+    /// Set the odds offered on each option of a fixed-odds market.
+    async fn set_odds(&mut self, market_id: u64, odds: Vec<u32>) -> OperationResponse {
+        let mut market = match self.state.get_market(market_id).await {
+            Some(m) => m,
+            None => {
+                return OperationResponse::Error {
+                    message: "Market not found".into(),
+                }
+            }
+        };
+
+        if let Err(response) = self
+            .authorize_market_operator(&market, &[Role::RiskManager])
+            .await
+        {
+            return response;
+        }
+
+        if market.pricing != PricingMode::FixedOdds {
+            return OperationResponse::Error {
+                message: "Market does not use fixed odds".into(),
+            };
+        }
+
+        if !matches!(market.status, MarketStatus::Open | MarketStatus::Suspended) {
+            return OperationResponse::Error {
+                message: "Market is not open".into(),
+            };
+        }
+
+        let config = market.odds_config;
+        let out_of_range = |o: u32| o <= 1000 || o < config.floor || o > config.cap;
+        if odds.len() != market.options.len()
+            || market
+                .options
+                .iter()
+                .zip(&odds)
+                .any(|(option, &o)| !option.voided && out_of_range(o))
+        {
+            return OperationResponse::Error {
+                message: "Odds must be above 1x and within the market's floor and cap".into(),
+            };
+        }
+
+        // Voided options stay without odds
+        for (option, odds) in market.options.iter_mut().zip(odds) {
+            if !option.voided {
+                option.odds = odds;
+            }
+        }
+        self.state.markets.insert(&market_id, market).expect("Failed to update odds");
+
+        OperationResponse::OddsUpdated { market_id }
+    }
+
+    /// Move funds from the caller's balance into the house liquidity pool.
+    async fn fund_house(&mut self, currency: Currency, amount: Amount) -> OperationResponse {
+        let owner = match self.authorize_role(Role::TreasuryManager).await {
+            Ok(owner) => owner,
+            Err(response) => return response,
+        };
+
+        let balance = self.state.get_balance(&owner, &currency).await;
+        if amount == 0 || balance < amount {
+            return OperationResponse::Error {
+                message: "Insufficient balance".into(),
+            };
+        }
+
+        let liquidity = self.state.get_house_liquidity(&currency).await + amount;
+        self.post(
+            LedgerAccount::Owner(owner),
+            LedgerAccount::House,
+            &currency,
+            amount,
+            LedgerReason::HouseFunding,
+            None,
+            None,
+        )
+        .await;
+        self.state.set_house_liquidity(&currency, liquidity);
+
+        OperationResponse::HouseFunded { amount, liquidity }
+    }
+
+    /// Move unreserved house liquidity to the caller's balance.
+    async fn withdraw_house(&mut self, currency: Currency, amount: Amount) -> OperationResponse {
+        let owner = match self.authorize_role(Role::TreasuryManager).await {
+            Ok(owner) => owner,
+            Err(response) => return response,
+        };
+
+        let liquidity = self.state.get_house_liquidity(&currency).await;
+        if amount == 0 || amount > liquidity {
+            return OperationResponse::Error {
+                message: "Insufficient house liquidity".into(),
+            };
+        }
+
+        self.post(
+            LedgerAccount::House,
+            LedgerAccount::Owner(owner),
+            &currency,
+            amount,
+            LedgerReason::HouseWithdrawal,
+            None,
+            None,
+        )
+        .await;
+        self.state.set_house_liquidity(&currency, liquidity - amount);
+
+        OperationResponse::HouseWithdrawn {
+            amount,
+            liquidity: liquidity - amount,
+        }
+    }
+
+    /// Set the global stake limits applying to markets without their own.
+    async fn set_stake_limits(&mut self, limits: StakeLimits) -> OperationResponse {
+        if let Err(response) = self.authorize_admin() {
+            return response;
+        }
+
+        if !valid_limits(&limits) {
+            return OperationResponse::Error {
+                message: "Stake limits must be greater than 0".into(),
+            };
+        }
+
+        self.state.stake_limits.set(limits);
+
+        OperationResponse::StakeLimitsUpdated { limits }
+    }
+
+    /// Grant a role to an owner.
+    async fn grant_role(&mut self, owner: AccountOwner, role: Role) -> OperationResponse {
+        if let Err(response) = self.authorize_admin() {
+            return response;
+        }
+
+        if !self.state.grant_role(&owner, role).await {
+            return OperationResponse::Error {
+                message: "Role already granted".into(),
+            };
+        }
+
+        OperationResponse::RoleGranted { owner, role }
+    }
+
+    /// Revoke a role from an owner.
+    async fn revoke_role(&mut self, owner: AccountOwner, role: Role) -> OperationResponse {
+        if let Err(response) = self.authorize_admin() {
+            return response;
+        }
+
+        if !self.state.revoke_role(&owner, role).await {
+            return OperationResponse::Error {
+                message: "Role not granted".into(),
+            };
+        }
+
+        OperationResponse::RoleRevoked { owner, role }
+    }
+
+    /// Move accumulated protocol fees to the caller's balance.
+    async fn withdraw_fees(&mut self, currency: Currency, amount: Amount) -> OperationResponse {
+        let owner = match self.authorize_role(Role::TreasuryManager).await {
+            Ok(owner) => owner,
+            Err(response) => return response,
+        };
+
+        let fees = self.state.get_protocol_fees(&currency).await;
+        if amount == 0 || amount > fees {
+            return OperationResponse::Error {
+                message: "Invalid fee withdrawal amount".into(),
+            };
+        }
+
+        self.state.set_protocol_fees(&currency, fees - amount);
+        self.post(
+            LedgerAccount::Protocol,
+            LedgerAccount::Owner(owner),
+            &currency,
+            amount,
+            LedgerReason::FeeWithdrawal,
+            None,
+            None,
+        )
+        .await;
+        let new_balance = self.state.get_balance(&owner, &currency).await;
+
+        OperationResponse::FeesWithdrawn {
+            amount,
+            new_balance,
+        }
     }
 
     /// Claim winnings for a bet.
     async fn claim_winnings(&mut self, bet_id: u64) -> OperationResponse {
-        let owner = self.caller_id();
+        let owner = match self.signer() {
+            Ok(owner) => owner,
+            Err(response) => return response,
+        };
 
         let mut bet = match self.state.get_bet(bet_id).await {
             Some(b) => b,
@@ -398,73 +1765,543 @@ impl LivePredictContract {
             }
         };
 
-        if bet.owner != owner {
+        if bet.owner != owner {
+            return OperationResponse::Error {
+                message: "Not your bet".into(),
+            };
+        }
+
+        if bet.settled {
+            return OperationResponse::Error {
+                message: "Bet already settled".into(),
+            };
+        }
+
+        let market = match self.state.get_market(bet.market_id).await {
+            Some(m) => m,
+            None => {
+                return OperationResponse::Error {
+                    message: "Market not found".into(),
+                }
+            }
+        };
+
+        if market.status != MarketStatus::Resolved {
+            return OperationResponse::Error {
+                message: "Market not yet resolved".into(),
+            };
+        }
+
+        let weight = LivePredictState::payout_weights(&market)[bet.option_id as usize];
+
+        let (gross, fee) = match market.pricing {
+            PricingMode::Parimutuel if LivePredictState::is_refunded(&market) => {
+                // Nobody backed a winner, refund the stake
+                (bet.amount, 0)
+            }
+            PricingMode::Parimutuel if weight > 0 => {
+                // Winner! Share of the option's part of the final pool, fee already
+                // taken at resolution
+                let gross = LivePredictState::calculate_parimutuel_payout(
+                    bet.amount,
+                    market.options[bet.option_id as usize].pool,
+                    LivePredictState::option_payout_pool(&market, bet.option_id),
+                );
+                (gross, 0)
+            }
+            PricingMode::FixedOdds | PricingMode::Lmsr if weight > 0 => {
+                // Winner! Each share pays its weight of one unit from the house-backed
+                // payout pool
+                let gross = bet.shares * weight as Amount / 10000;
+                let fee_rate = *self.state.fee_rate_bps.get();
+                (gross, LivePredictState::calculate_fee(gross, fee_rate))
+            }
+            // Lost
+            _ => (0, 0),
+        };
+        let payout = gross - fee;
+
+        // Update balance
+        self.state.unlock_stake(&owner, &market.currency, bet.amount).await;
+        let escrow = LedgerAccount::Market(market.id);
+        self.post(
+            escrow,
+            LedgerAccount::Owner(owner),
+            &market.currency,
+            payout,
+            LedgerReason::Payout,
+            Some(market.id),
+            Some(bet_id),
+        )
+        .await;
+        self.take_fee(escrow, Some(market.id), &market.currency, fee, Some(bet_id)).await;
+
+        // Mark as settled
+        bet.settled = true;
+        bet.payout = Some(payout);
+        self.state.bets.insert(&bet_id, bet).expect("Failed to settle bet");
+
+        OperationResponse::WinningsClaimed {
+            bet_id,
+            amount: payout,
+        }
+    }
+
+    /// Load a bet owned by the signer that can be cashed out, with its open market.
+    async fn cashable_bet(&mut self, bet_id: u64) -> Result<(Bet, Market), OperationResponse> {
+        let owner = self.signer()?;
+
+        let bet = self
+            .state
+            .get_bet(bet_id)
+            .await
+            .ok_or_else(|| OperationResponse::Error {
+                message: "Bet not found".into(),
+            })?;
+
+        if bet.owner != owner {
+            return Err(OperationResponse::Error {
+                message: "Not your bet".into(),
+            });
+        }
+
+        if bet.settled {
+            return Err(OperationResponse::Error {
+                message: "Bet already settled".into(),
+            });
+        }
+
+        let market = self
+            .state
+            .get_market(bet.market_id)
+            .await
+            .ok_or_else(|| OperationResponse::Error {
+                message: "Market not found".into(),
+            })?;
+
+        if market.status != MarketStatus::Open || self.current_time() >= market.locks_at {
+            return Err(OperationResponse::Error {
+                message: "Market is not open for cash-out".into(),
+            });
+        }
+
+        Ok((bet, market))
+    }
+
+    /// Cash out the stake and shares of `exit` at the market's current pricing:
+    /// remove them from the option, take the protocol fee and credit the owner.
+    /// Returns the amount credited.
+    async fn settle_cash_out(
+        &mut self,
+        market: &mut Market,
+        exit: &Bet,
+        min_value: Amount,
+    ) -> Result<Amount, OperationResponse> {
+        let value = LivePredictState::cash_out_value(market, exit).ok_or_else(|| {
+            OperationResponse::Error {
+                message: "Cash-out unavailable for this bet".into(),
+            }
+        })?;
+
+        let fee_rate = *self.state.fee_rate_bps.get();
+        let fee = LivePredictState::calculate_fee(value, fee_rate);
+        let payout = value - fee;
+        if payout < min_value {
+            return Err(OperationResponse::CashOutBelowMinimum {
+                value: payout,
+                min_value,
+            });
+        }
+
+        // Remove the stake from the option pool
+        let house_before = self.state.get_house_liquidity(&market.currency).await;
+        match market.pricing {
+            PricingMode::Parimutuel => {
+                market.options[exit.option_id as usize].pool -= exit.amount;
+                market.retained += exit.amount - value;
+            }
+            PricingMode::FixedOdds | PricingMode::Lmsr => {
+                if !self
+                    .state
+                    .cash_out_house(market, exit.option_id, exit.amount, exit.shares, value)
+                    .await
+                {
+                    return Err(OperationResponse::Error {
+                        message: "Cash-out exceeds house liquidity".into(),
+                    });
+                }
+            }
+        }
+
+        self.state.unlock_stake(&exit.owner, &market.currency, exit.amount).await;
+        self.state.remove_owner_stake(market.id, &exit.owner, exit.amount).await;
+        let escrow = LedgerAccount::Market(market.id);
+        self.post(
+            escrow,
+            LedgerAccount::Owner(exit.owner),
+            &market.currency,
+            payout,
+            LedgerReason::CashOut,
+            Some(market.id),
+            Some(exit.id),
+        )
+        .await;
+        self.take_fee(escrow, Some(market.id), &market.currency, fee, Some(exit.id)).await;
+        self.post_house_change(escrow, Some(market.id), &market.currency, house_before).await;
+
+        Ok(payout)
+    }
+
+    /// Sell a bet back at its current value while the market is open.
+    async fn cash_out(&mut self, bet_id: u64, min_value: Amount) -> OperationResponse {
+        let (mut bet, mut market) = match self.cashable_bet(bet_id).await {
+            Ok(loaded) => loaded,
+            Err(response) => return response,
+        };
+
+        let payout = match self.settle_cash_out(&mut market, &bet, min_value).await {
+            Ok(payout) => payout,
+            Err(response) => return response,
+        };
+
+        bet.settled = true;
+        bet.cashed_out = true;
+        bet.payout = Some(payout);
+        self.state.markets.insert(&bet.market_id, market).expect("Failed to update market");
+        self.state.bets.insert(&bet_id, bet).expect("Failed to cash out bet");
+
+        OperationResponse::CashedOut {
+            bet_id,
+            amount: payout,
+        }
+    }
+
+    /// Sell part of a bet back at its current value while the market is open.
+    /// The exited part is split off into a new, settled bet record.
+    async fn partial_cash_out(
+        &mut self,
+        bet_id: u64,
+        fraction_bps: u32,
+        min_value: Amount,
+    ) -> OperationResponse {
+        if fraction_bps == 0 || fraction_bps >= 10000 {
             return OperationResponse::Error {
-                message: "Not your bet".into(),
+                message: "Fraction must be between 1 and 9999 basis points".into(),
             };
         }
 
-        if bet.settled {
+        let (mut bet, mut market) = match self.cashable_bet(bet_id).await {
+            Ok(loaded) => loaded,
+            Err(response) => return response,
+        };
+
+        let mut exit = bet.clone();
+        exit.amount = bet.amount * fraction_bps as Amount / 10000;
+        exit.shares = bet.shares * fraction_bps as Amount / 10000;
+        if exit.amount == 0 {
             return OperationResponse::Error {
-                message: "Bet already settled".into(),
+                message: "Cash-out amount too small".into(),
             };
         }
 
-        let market = match self.state.get_market(bet.market_id).await {
-            Some(m) => m,
-            None => {
+        let payout = match self.settle_cash_out(&mut market, &exit, min_value).await {
+            Ok(payout) => payout,
+            Err(response) => return response,
+        };
+
+        // Split the bet into a settled record and the remaining live bet
+        let exit_bet_id = self.state.allocate_bet_id().await;
+        exit.id = exit_bet_id;
+        exit.settled = true;
+        exit.cashed_out = true;
+        exit.payout = Some(payout);
+        exit.split_from = Some(bet_id);
+        bet.amount -= exit.amount;
+        bet.shares -= exit.shares;
+        let remaining = bet.amount;
+
+        self.state.add_user_bet(&bet.owner, exit_bet_id).await;
+        self.state.add_market_bet(bet.market_id, exit_bet_id).await;
+        self.state.add_bet_split(bet_id, exit_bet_id).await;
+        self.state.markets.insert(&bet.market_id, market).expect("Failed to update market");
+        self.state.bets.insert(&exit_bet_id, exit).expect("Failed to split bet");
+        self.state.bets.insert(&bet_id, bet).expect("Failed to update bet");
+
+        OperationResponse::PartiallyCashedOut {
+            bet_id,
+            exit_bet_id,
+            amount: payout,
+            remaining,
+        }
+    }
+
+    /// Place a single stake on several market options, each on a different market.
+    /// The combined odds are the product of each leg's current odds and the payout is
+    /// backed by house liquidity.
+    async fn place_parlay(&mut self, legs: Vec<(u64, u8)>, amount: Amount) -> OperationResponse {
+        let owner = match self.signer() {
+            Ok(owner) => owner,
+            Err(response) => return response,
+        };
+
+        if legs.len() < 2 || legs.len() > 10 {
+            return OperationResponse::Error {
+                message: "Parlay must have 2-10 legs".into(),
+            };
+        }
+
+        if amount == 0 {
+            return OperationResponse::Error {
+                message: "Bet amount must be greater than 0".into(),
+            };
+        }
+
+        // Price each leg at its market's current odds
+        let now = self.current_time();
+        let mut currency: Option<Currency> = None;
+        let mut parlay_legs: Vec<ParlayLeg> = Vec::new();
+        let mut combined_odds: Amount = 1000;
+        for (market_id, option_id) in legs {
+            if parlay_legs.iter().any(|leg| leg.market_id == market_id) {
                 return OperationResponse::Error {
-                    message: "Market not found".into(),
+                    message: "Parlay legs must be on different markets".into(),
+                };
+            }
+
+            let market = match self.state.get_market(market_id).await {
+                Some(m) => m,
+                None => {
+                    return OperationResponse::Error {
+                        message: "Market not found".into(),
+                    }
                 }
+            };
+
+            if market.status != MarketStatus::Open || now >= market.locks_at {
+                return OperationResponse::Error {
+                    message: "Market is not open for betting".into(),
+                };
+            }
+
+            if market.kind == MarketKind::Scalar {
+                return OperationResponse::Error {
+                    message: "Scalar markets can't be used in parlays".into(),
+                };
+            }
+
+            let parlay_currency = currency.get_or_insert_with(|| market.currency.clone());
+            if *parlay_currency != market.currency {
+                return OperationResponse::Error {
+                    message: "Parlay legs must be in the same currency".into(),
+                };
+            }
+
+            if option_id as usize >= market.options.len() {
+                return OperationResponse::Error {
+                    message: "Invalid option".into(),
+                };
             }
+
+            let Some(odds) = LivePredictState::current_odds(&market, option_id) else {
+                return OperationResponse::Error {
+                    message: "Odds not set for this option".into(),
+                };
+            };
+
+            let Some(product) = combined_odds.checked_mul(odds as Amount) else {
+                return OperationResponse::Error {
+                    message: "Parlay odds too high".into(),
+                };
+            };
+            combined_odds = product / 1000;
+            parlay_legs.push(ParlayLeg {
+                market_id,
+                option_id,
+                odds,
+            });
+        }
+
+        let Ok(odds) = u32::try_from(combined_odds) else {
+            return OperationResponse::Error {
+                message: "Parlay odds too high".into(),
+            };
         };
 
-        if market.status != MarketStatus::Resolved {
+        let currency = currency.expect("Parlay has legs");
+        let balance = self.state.get_balance(&owner, &currency).await;
+        if balance < amount {
             return OperationResponse::Error {
-                message: "Market not yet resolved".into(),
+                message: "Insufficient balance".into(),
             };
         }
 
-        let winning_option = market.winning_option.unwrap();
-        let fee_rate = *self.state.fee_rate_bps.get();
+        // Reserve the potential winnings from the house
+        let potential_payout = LivePredictState::calculate_fixed_payout(amount, odds);
+        let house_reserve = potential_payout.saturating_sub(amount);
+        if !self.state.reserve_parlay(&currency, house_reserve).await {
+            return OperationResponse::Error {
+                message: "Parlay exceeds house liquidity".into(),
+            };
+        }
 
-        let payout = if bet.option_id == winning_option {
-            // Winner! Calculate payout
-            LivePredictState::calculate_payout(bet.amount, bet.odds, fee_rate)
-        } else {
-            // Lost
-            0
+        // Move the stake from the available balance into the locked balance
+        let parlay_id = self.state.allocate_parlay_id().await;
+        let escrow = LedgerAccount::Parlay(parlay_id);
+        self.post(
+            LedgerAccount::Owner(owner),
+            escrow,
+            &currency,
+            amount,
+            LedgerReason::ParlayStake,
+            None,
+            Some(parlay_id),
+        )
+        .await;
+        self.post(
+            LedgerAccount::House,
+            escrow,
+            &currency,
+            house_reserve,
+            LedgerReason::HouseReserve,
+            None,
+            None,
+        )
+        .await;
+        self.state.lock_stake(&owner, &currency, amount).await;
+        self.state.add_total_volume(&currency, amount).await;
+
+        let parlay = Parlay {
+            id: parlay_id,
+            owner,
+            currency,
+            legs: parlay_legs,
+            amount,
+            odds,
+            potential_payout,
+            house_reserve,
+            placed_at: now,
+            settled: false,
+            payout: None,
         };
 
-        // Update balance
-        if payout > 0 {
-            let balance = self.state.get_balance(&owner).await;
-            self.state.set_balance(&owner, balance + payout).await;
-            
-            // Track protocol fees
-            let gross = (bet.amount as u64 * bet.odds as u64) / 1000;
-            let fee = gross - payout as u64;
-            let current_fees = *self.state.protocol_fees.get();
-            self.state.protocol_fees.set(current_fees + fee as Amount);
+        self.state.parlays.insert(&parlay_id, parlay).expect("Failed to create parlay");
+        self.state.add_user_parlay(&owner, parlay_id).await;
+
+        OperationResponse::ParlayPlaced { parlay_id, odds }
+    }
+
+    /// Settle a parlay once all its legs are settled or any leg lost, crediting the
+    /// owner. Void legs drop out of the combined odds, and a parlay whose legs are all
+    /// void is refunded. Anyone can settle a parlay.
+    async fn settle_parlay(&mut self, parlay_id: u64) -> OperationResponse {
+        let mut parlay = match self.state.get_parlay(parlay_id).await {
+            Some(p) => p,
+            None => {
+                return OperationResponse::Error {
+                    message: "Parlay not found".into(),
+                }
+            }
+        };
+
+        if parlay.settled {
+            return OperationResponse::Error {
+                message: "Parlay already settled".into(),
+            };
         }
 
-        // Mark as settled
-        bet.settled = true;
-        bet.payout = Some(payout);
-        self.state.bets.insert(&bet_id, bet).expect("Failed to settle bet");
+        let mut legs = Vec::new();
+        let mut weights = Vec::new();
+        for leg in &parlay.legs {
+            let market = match self.state.get_market(leg.market_id).await {
+                Some(m) => m,
+                None => {
+                    return OperationResponse::Error {
+                        message: "Market not found".into(),
+                    }
+                }
+            };
+            legs.push(LivePredictState::leg_status(&market, leg.option_id));
+            weights.push(LivePredictState::payout_weights(&market)[leg.option_id as usize]);
+        }
 
-        OperationResponse::WinningsClaimed {
-            bet_id,
+        let (gross, fee) = match LivePredictState::parlay_status(&legs) {
+            LegStatus::Pending => {
+                return OperationResponse::Error {
+                    message: "Parlay legs not yet settled".into(),
+                }
+            }
+            LegStatus::Lost => (0, 0),
+            // Every leg void, refund the stake
+            LegStatus::Void => (parlay.amount, 0),
+            LegStatus::Won => {
+                let odds = LivePredictState::settled_parlay_odds(&parlay, &weights);
+                let gross = (parlay.amount * odds / 1000).min(parlay.potential_payout);
+                let fee_rate = *self.state.fee_rate_bps.get();
+                (gross, LivePredictState::calculate_fee(gross, fee_rate))
+            }
+        };
+
+        let house_before = self.state.get_house_liquidity(&parlay.currency).await;
+        self.state.settle_parlay_house(&parlay, gross).await;
+
+        let payout = gross - fee;
+        self.state.unlock_stake(&parlay.owner, &parlay.currency, parlay.amount).await;
+        let escrow = LedgerAccount::Parlay(parlay_id);
+        self.post(
+            escrow,
+            LedgerAccount::Owner(parlay.owner),
+            &parlay.currency,
+            payout,
+            LedgerReason::ParlayPayout,
+            None,
+            Some(parlay_id),
+        )
+        .await;
+        self.take_fee(escrow, None, &parlay.currency, fee, Some(parlay_id)).await;
+        self.post_house_change(escrow, None, &parlay.currency, house_before).await;
+
+        parlay.settled = true;
+        parlay.payout = Some(payout);
+        self.state.parlays.insert(&parlay_id, parlay).expect("Failed to settle parlay");
+
+        OperationResponse::ParlaySettled {
+            parlay_id,
             amount: payout,
         }
     }
 
     /// Deposit tokens to user balance.
-    async fn deposit(&mut self, amount: Amount) -> OperationResponse {
-        let owner = self.caller_id();
-        let balance = self.state.get_balance(&owner).await;
-        let new_balance = balance + amount;
-        self.state.set_balance(&owner, new_balance).await;
+    async fn deposit(&mut self, currency: Currency, amount: Amount) -> OperationResponse {
+        let owner = match self.signer() {
+            Ok(owner) => owner,
+            Err(response) => return response,
+        };
+
+        if let Err(response) = self.check_currency(&currency) {
+            return response;
+        }
+
+        if amount == 0 {
+            return OperationResponse::Error {
+                message: "Deposit amount must be greater than 0".into(),
+            };
+        }
+
+        // Balances are backed 1:1 by tokens held in the application's account
+        let application = self.runtime.application_id().into();
+        self.transfer_tokens(&currency, owner, application, amount);
+
+        self.post(
+            LedgerAccount::External(owner),
+            LedgerAccount::Owner(owner),
+            &currency,
+            amount,
+            LedgerReason::Deposit,
+            None,
+            None,
+        )
+        .await;
+        let new_balance = self.state.get_balance(&owner, &currency).await;
 
         OperationResponse::Deposited {
             amount,
@@ -473,18 +2310,33 @@ impl LivePredictContract {
     }
 
     /// Withdraw tokens from user balance.
-    async fn withdraw(&mut self, amount: Amount) -> OperationResponse {
-        let owner = self.caller_id();
-        let balance = self.state.get_balance(&owner).await;
+    async fn withdraw(&mut self, currency: Currency, amount: Amount) -> OperationResponse {
+        let owner = match self.signer() {
+            Ok(owner) => owner,
+            Err(response) => return response,
+        };
+        let balance = self.state.get_balance(&owner, &currency).await;
 
-        if balance < amount {
+        if amount == 0 || balance < amount {
             return OperationResponse::Error {
                 message: "Insufficient balance".into(),
             };
         }
 
-        let new_balance = balance - amount;
-        self.state.set_balance(&owner, new_balance).await;
+        let application = self.runtime.application_id().into();
+        self.transfer_tokens(&currency, application, owner, amount);
+
+        self.post(
+            LedgerAccount::Owner(owner),
+            LedgerAccount::External(owner),
+            &currency,
+            amount,
+            LedgerReason::Withdrawal,
+            None,
+            None,
+        )
+        .await;
+        let new_balance = self.state.get_balance(&owner, &currency).await;
 
         OperationResponse::Withdrawn {
             amount,
@@ -492,3 +2344,569 @@ impl LivePredictContract {
         }
     }
 }
+
+/// Check that every set stake limit is greater than 0.
+fn valid_limits(limits: &StakeLimits) -> bool {
+    [limits.max_stake, limits.max_owner_stake, limits.max_pool]
+        .into_iter()
+        .all(|limit| limit != Some(0))
+}
+
+/// Option labels of a new market, generated from the line or bounds of numeric markets.
+fn market_options(
+    kind: MarketKind,
+    line: Option<i64>,
+    bounds: &[i64],
+    options: Vec<String>,
+) -> Result<Vec<String>, OperationResponse> {
+    let error = |message: &str| -> Result<Vec<String>, OperationResponse> {
+        Err(OperationResponse::Error {
+            message: message.into(),
+        })
+    };
+
+    if !matches!(kind, MarketKind::Categorical | MarketKind::Handicap) && !options.is_empty() {
+        return error("Numeric markets generate their own options");
+    }
+    if !matches!(kind, MarketKind::OverUnder | MarketKind::Handicap) && line.is_some() {
+        return error("Line only applies to over/under and handicap markets");
+    }
+    if !matches!(kind, MarketKind::Range | MarketKind::Scalar) && !bounds.is_empty() {
+        return error("Bounds only apply to range and scalar markets");
+    }
+    if bounds.windows(2).any(|pair| pair[0] >= pair[1]) {
+        return error("Bounds must be increasing");
+    }
+
+    match kind {
+        MarketKind::Categorical => Ok(options),
+        MarketKind::OverUnder => {
+            let Some(line) = line else {
+                return error("Over/under markets need a line");
+            };
+            let line = format_scaled(line);
+            Ok(vec![format!("Over {line}"), format!("Under {line}")])
+        }
+        MarketKind::Handicap => {
+            let Some(line) = line else {
+                return error("Handicap markets need a spread");
+            };
+            let [team_a, team_b] = options.as_slice() else {
+                return error("Handicap markets need two teams");
+            };
+            // Team B gets the opposite spread, and resolution negates it too
+            let Some(opposite) = line.checked_neg() else {
+                return error("Spread out of range");
+            };
+            Ok(vec![
+                format!("{team_a} {}", format_spread(line)),
+                format!("{team_b} {}", format_spread(opposite)),
+            ])
+        }
+        MarketKind::Range => {
+            let (Some(&first), Some(&last)) = (bounds.first(), bounds.last()) else {
+                return error("Range markets need bucket boundaries");
+            };
+            let mut labels = vec![format!("Under {}", format_scaled(first))];
+            labels.extend(bounds.windows(2).map(|pair| {
+                format!("{} to {}", format_scaled(pair[0]), format_scaled(pair[1]))
+            }));
+            labels.push(format!("{} or more", format_scaled(last)));
+            Ok(labels)
+        }
+        MarketKind::Scalar => {
+            if bounds.len() != 2 {
+                return error("Scalar markets need a lower and upper bound");
+            }
+            Ok(vec!["Long".into(), "Short".into()])
+        }
+    }
+}
+
+/// Format a spread scaled by 1000 with its sign, e.g. -3500 as "-3.5".
+fn format_spread(spread: i64) -> String {
+    if spread > 0 {
+        format!("+{}", format_scaled(spread))
+    } else {
+        format_scaled(spread)
+    }
+}
+
+/// Format a value scaled by 1000 as a decimal, e.g. 6500 as "6.5".
+fn format_scaled(value: i64) -> String {
+    let sign = if value < 0 { "-" } else { "" };
+    let whole = value.unsigned_abs() / 1000;
+    let fraction = value.unsigned_abs() % 1000;
+    if fraction == 0 {
+        format!("{sign}{whole}")
+    } else {
+        let fraction = format!("{fraction:03}");
+        format!("{sign}{whole}.{}", fraction.trim_end_matches('0'))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use linera_sdk::{
+        linera_base_types::{
+            AccountOwner, Amount as TokenAmount, ApplicationId, ChainId, CryptoHash,
+        },
+        views::View,
+        Contract, ContractRuntime,
+    };
+    use live_predict::{
+        Amount, Backing, InstantiationArgument, LedgerAccount, LedgerReason, LivePredictParameters,
+        MarketKind, Operation, OperationResponse, PricingMode, StakeLimits, Timestamp,
+        NATIVE_CURRENCY,
+    };
+
+    use super::{market_options, LivePredictContract, LivePredictState};
+
+    /// Admin creating the markets in the tests.
+    const CREATOR: AccountOwner = AccountOwner::Address20([1; 20]);
+
+    /// Owners betting in the tests.
+    const ALICE: AccountOwner = AccountOwner::Address20([2; 20]);
+    const BOB: AccountOwner = AccountOwner::Address20([3; 20]);
+
+    /// Instantiate the contract with the native token backing balances, signed by
+    /// `CREATOR`.
+    async fn create_contract() -> LivePredictContract {
+        let runtime = ContractRuntime::new()
+            .with_application_parameters(LivePredictParameters {
+                admins: vec![CREATOR],
+                tokens: BTreeMap::new(),
+            })
+            .with_chain_id(ChainId::root(0))
+            .with_application_id(
+                ApplicationId::new(CryptoHash::test_hash("live-predict")).with_abi(),
+            )
+            .with_authenticated_signer(CREATOR)
+            .with_system_time(Timestamp::from(0));
+        let state = LivePredictState::load(runtime.root_view_storage_context())
+            .await
+            .expect("Failed to load state");
+        let mut contract = LivePredictContract { state, runtime };
+        contract
+            .instantiate(InstantiationArgument {
+                fee_rate_bps: 100,
+                backing: Backing::Native,
+            })
+            .await;
+        contract
+    }
+
+    /// Credit an owner's balance as a deposit would.
+    async fn fund(contract: &mut LivePredictContract, owner: AccountOwner, amount: Amount) {
+        contract
+            .post(
+                LedgerAccount::External(owner),
+                LedgerAccount::Owner(owner),
+                &NATIVE_CURRENCY.to_string(),
+                amount,
+                LedgerReason::Deposit,
+                None,
+                None,
+            )
+            .await;
+    }
+
+    /// Create a parimutuel market of a kind with its line, seeded by `CREATOR`.
+    /// Categorical and handicap markets are between two teams.
+    async fn create_market(
+        contract: &mut LivePredictContract,
+        kind: MarketKind,
+        line: Option<i64>,
+        seed: Vec<Amount>,
+    ) -> u64 {
+        let options = match kind {
+            MarketKind::Categorical | MarketKind::Handicap => {
+                vec!["Team A".into(), "Team B".into()]
+            }
+            _ => Vec::new(),
+        };
+        let response = contract
+            .execute_operation(Operation::CreateMarket {
+                match_id: "match".into(),
+                market_type: "match_winner".into(),
+                title: "Who wins?".into(),
+                options,
+                locks_at: Timestamp::from(1_000_000),
+                kind,
+                line,
+                bounds: Vec::new(),
+                pricing: PricingMode::Parimutuel,
+                liquidity: 0,
+                condition: None,
+                limits: StakeLimits::default(),
+                odds_config: None,
+                seed,
+                currency: NATIVE_CURRENCY.into(),
+            })
+            .await;
+        match response {
+            OperationResponse::MarketCreated { market_id } => market_id,
+            response => panic!("Unexpected response {response:?}"),
+        }
+    }
+
+    /// Place a bet signed by `owner`, returning its ID.
+    async fn place_bet(
+        contract: &mut LivePredictContract,
+        owner: AccountOwner,
+        market_id: u64,
+        option_id: u8,
+        amount: Amount,
+    ) -> u64 {
+        contract.runtime.set_authenticated_signer(owner);
+        let response = contract
+            .execute_operation(Operation::PlaceBet {
+                market_id,
+                option_id,
+                amount,
+                min_odds: None,
+            })
+            .await;
+        contract.runtime.set_authenticated_signer(CREATOR);
+        match response {
+            OperationResponse::BetPlaced { bet_id, .. } => bet_id,
+            response => panic!("Unexpected response {response:?}"),
+        }
+    }
+
+    async fn balance(contract: &LivePredictContract, owner: AccountOwner) -> Amount {
+        contract.state.get_balance(&owner, &NATIVE_CURRENCY.to_string()).await
+    }
+
+    async fn locked(contract: &LivePredictContract, owner: AccountOwner) -> Amount {
+        contract.state.get_locked(&owner, &NATIVE_CURRENCY.to_string()).await
+    }
+
+    /// Native tokens an owner holds on the chain, outside the application.
+    fn tokens(contract: &mut LivePredictContract, owner: AccountOwner) -> TokenAmount {
+        contract.runtime.owner_balance(owner)
+    }
+
+    /// Execute a deposit or withdrawal signed by `owner`.
+    async fn execute_as(
+        contract: &mut LivePredictContract,
+        owner: AccountOwner,
+        operation: Operation,
+    ) -> OperationResponse {
+        contract.runtime.set_authenticated_signer(owner);
+        let response = contract.execute_operation(operation).await;
+        contract.runtime.set_authenticated_signer(CREATOR);
+        response
+    }
+
+    /// Debit, credit, amount and reason of every ledger entry, oldest first.
+    async fn ledger(
+        contract: &LivePredictContract,
+    ) -> Vec<(LedgerAccount, LedgerAccount, Amount, LedgerReason)> {
+        let entries = contract.state.ledger.read(..).await.expect("Failed to read ledger");
+        entries
+            .into_iter()
+            .map(|entry| (entry.debit, entry.credit, entry.amount, entry.reason))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn cancelling_twice_returns_the_seed_once() {
+        let mut contract = create_contract().await;
+        fund(&mut contract, CREATOR, 300).await;
+        let market_id =
+            create_market(&mut contract, MarketKind::Categorical, None, vec![100, 200]).await;
+        assert_eq!(balance(&contract, CREATOR).await, 0);
+        assert_eq!(locked(&contract, CREATOR).await, 300);
+
+        let response = contract.execute_operation(Operation::CancelMarket { market_id }).await;
+        assert!(matches!(response, OperationResponse::MarketCancelled { .. }));
+        assert_eq!(balance(&contract, CREATOR).await, 300);
+        assert_eq!(locked(&contract, CREATOR).await, 0);
+
+        let response = contract.execute_operation(Operation::CancelMarket { market_id }).await;
+        assert!(matches!(response, OperationResponse::Error { .. }));
+        assert_eq!(balance(&contract, CREATOR).await, 300);
+        assert_eq!(locked(&contract, CREATOR).await, 0);
+
+        // The seed's stake and return name the market, though no bet is involved
+        let entries = contract.state.ledger.read(..).await.expect("Failed to read ledger");
+        let seed_entries: Vec<_> = entries
+            .iter()
+            .filter(|entry| matches!(entry.reason, LedgerReason::Seed | LedgerReason::SeedReturn))
+            .collect();
+        assert_eq!(seed_entries.len(), 2);
+        assert!(seed_entries.iter().all(|entry| entry.market_id == Some(market_id)));
+    }
+
+    #[tokio::test]
+    async fn resolving_unlocks_losing_stakes() {
+        let mut contract = create_contract().await;
+        fund(&mut contract, ALICE, 100).await;
+        fund(&mut contract, BOB, 50).await;
+        let market_id =
+            create_market(&mut contract, MarketKind::Categorical, None, Vec::new()).await;
+        let winning_bet = place_bet(&mut contract, ALICE, market_id, 0, 100).await;
+        let losing_bet = place_bet(&mut contract, BOB, market_id, 1, 50).await;
+        assert_eq!(locked(&contract, ALICE).await, 100);
+        assert_eq!(locked(&contract, BOB).await, 50);
+
+        let response = contract
+            .execute_operation(Operation::ResolveMarket {
+                market_id,
+                winning_option: 0,
+            })
+            .await;
+        assert!(matches!(response, OperationResponse::MarketResolved { .. }));
+        assert_eq!(locked(&contract, ALICE).await, 100);
+        assert_eq!(locked(&contract, BOB).await, 0);
+        let bet = contract.state.get_bet(losing_bet).await.unwrap();
+        assert!(bet.settled);
+        assert_eq!(bet.payout, Some(0));
+
+        contract.runtime.set_authenticated_signer(ALICE);
+        let response = contract
+            .execute_operation(Operation::ClaimWinnings {
+                bet_id: winning_bet,
+            })
+            .await;
+        assert!(matches!(response, OperationResponse::WinningsClaimed { amount: 149, .. }));
+        assert_eq!(locked(&contract, ALICE).await, 0);
+        assert_eq!(balance(&contract, ALICE).await, 149);
+    }
+
+    #[tokio::test]
+    async fn numeric_markets_are_only_resolved_by_value() {
+        let mut contract = create_contract().await;
+        let market_id =
+            create_market(&mut contract, MarketKind::OverUnder, Some(6500), Vec::new()).await;
+
+        let response = contract
+            .execute_operation(Operation::ResolveMarket {
+                market_id,
+                winning_option: 0,
+            })
+            .await;
+        assert!(matches!(response, OperationResponse::Error { .. }));
+        let response = contract
+            .execute_operation(Operation::ResolveWinners {
+                market_id,
+                winners: vec![(0, None)],
+            })
+            .await;
+        assert!(matches!(response, OperationResponse::Error { .. }));
+
+        let response = contract
+            .execute_operation(Operation::ResolveScalar {
+                market_id,
+                value: 6500,
+            })
+            .await;
+        assert!(matches!(response, OperationResponse::MarketPushed { .. }));
+    }
+
+    #[tokio::test]
+    async fn handicap_markets_are_only_resolved_by_score() {
+        let mut contract = create_contract().await;
+        let market_id =
+            create_market(&mut contract, MarketKind::Handicap, Some(-3500), Vec::new()).await;
+
+        let response = contract
+            .execute_operation(Operation::ResolveScalar {
+                market_id,
+                value: 4000,
+            })
+            .await;
+        assert!(matches!(response, OperationResponse::Error { .. }));
+
+        let response = contract
+            .execute_operation(Operation::ResolveScore {
+                market_id,
+                score_a: 13,
+                score_b: 9,
+            })
+            .await;
+        assert!(matches!(
+            response,
+            OperationResponse::MarketResolved {
+                winning_option: 0,
+                ..
+            }
+        ));
+    }
+
+    #[tokio::test]
+    async fn range_buckets_cant_be_voided() {
+        let mut contract = create_contract().await;
+        let response = contract
+            .execute_operation(Operation::CreateMarket {
+                match_id: "match".into(),
+                market_type: "total_kills".into(),
+                title: "Total kills".into(),
+                options: Vec::new(),
+                locks_at: Timestamp::from(1_000_000),
+                kind: MarketKind::Range,
+                line: None,
+                bounds: vec![10_000, 20_000],
+                pricing: PricingMode::Parimutuel,
+                liquidity: 0,
+                condition: None,
+                limits: StakeLimits::default(),
+                odds_config: None,
+                seed: Vec::new(),
+                currency: NATIVE_CURRENCY.into(),
+            })
+            .await;
+        let OperationResponse::MarketCreated { market_id } = response else {
+            panic!("Unexpected response {response:?}");
+        };
+
+        let response = contract
+            .execute_operation(Operation::VoidOption {
+                market_id,
+                option_id: 1,
+            })
+            .await;
+        assert!(matches!(response, OperationResponse::Error { .. }));
+
+        let response = contract
+            .execute_operation(Operation::ResolveScalar {
+                market_id,
+                value: 15_000,
+            })
+            .await;
+        assert!(matches!(
+            response,
+            OperationResponse::MarketResolved {
+                winning_option: 1,
+                ..
+            }
+        ));
+    }
+
+    #[tokio::test]
+    async fn deposits_and_withdrawals_move_native_tokens() {
+        let mut contract = create_contract().await;
+        let application = AccountOwner::from(contract.runtime.application_id());
+        contract.runtime.set_owner_balances([
+            (ALICE, TokenAmount::from_micros(1000)),
+            (application, TokenAmount::ZERO),
+        ]);
+        let currency = NATIVE_CURRENCY.to_string();
+
+        let deposit = Operation::Deposit {
+            currency: currency.clone(),
+            amount: 600,
+        };
+        let response = execute_as(&mut contract, ALICE, deposit).await;
+        assert!(matches!(
+            response,
+            OperationResponse::Deposited {
+                amount: 600,
+                new_balance: 600
+            }
+        ));
+        assert_eq!(tokens(&mut contract, ALICE), TokenAmount::from_micros(400));
+        assert_eq!(
+            tokens(&mut contract, application),
+            TokenAmount::from_micros(600)
+        );
+
+        let withdrawal = Operation::Withdraw {
+            currency,
+            amount: 200,
+        };
+        let response = execute_as(&mut contract, ALICE, withdrawal).await;
+        assert!(matches!(
+            response,
+            OperationResponse::Withdrawn {
+                amount: 200,
+                new_balance: 400
+            }
+        ));
+        assert_eq!(tokens(&mut contract, ALICE), TokenAmount::from_micros(600));
+        assert_eq!(
+            tokens(&mut contract, application),
+            TokenAmount::from_micros(400)
+        );
+        assert_eq!(balance(&contract, ALICE).await, 400);
+
+        assert_eq!(
+            ledger(&contract).await,
+            [
+                (
+                    LedgerAccount::External(ALICE),
+                    LedgerAccount::Owner(ALICE),
+                    600,
+                    LedgerReason::Deposit,
+                ),
+                (
+                    LedgerAccount::Owner(ALICE),
+                    LedgerAccount::External(ALICE),
+                    200,
+                    LedgerReason::Withdrawal,
+                ),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn withdrawing_more_than_the_balance_transfers_nothing() {
+        let mut contract = create_contract().await;
+        let application = AccountOwner::from(contract.runtime.application_id());
+        contract.runtime.set_owner_balances([
+            (ALICE, TokenAmount::from_micros(1000)),
+            (application, TokenAmount::ZERO),
+        ]);
+        let currency = NATIVE_CURRENCY.to_string();
+        let deposit = Operation::Deposit {
+            currency: currency.clone(),
+            amount: 100,
+        };
+        execute_as(&mut contract, ALICE, deposit).await;
+
+        let withdrawal = Operation::Withdraw {
+            currency,
+            amount: 101,
+        };
+        let response = execute_as(&mut contract, ALICE, withdrawal).await;
+        assert!(matches!(response, OperationResponse::Error { .. }));
+        assert_eq!(tokens(&mut contract, ALICE), TokenAmount::from_micros(900));
+        assert_eq!(
+            tokens(&mut contract, application),
+            TokenAmount::from_micros(100)
+        );
+        assert_eq!(balance(&contract, ALICE).await, 100);
+        assert_eq!(ledger(&contract).await.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn deposits_in_unknown_currencies_are_rejected() {
+        let mut contract = create_contract().await;
+        let application = AccountOwner::from(contract.runtime.application_id());
+        contract.runtime.set_owner_balances([
+            (ALICE, TokenAmount::from_micros(1000)),
+            (application, TokenAmount::ZERO),
+        ]);
+
+        let deposit = Operation::Deposit {
+            currency: "USDC".into(),
+            amount: 100,
+        };
+        let response = execute_as(&mut contract, ALICE, deposit).await;
+        assert!(matches!(response, OperationResponse::Error { .. }));
+        assert_eq!(tokens(&mut contract, ALICE), TokenAmount::from_micros(1000));
+        assert_eq!(tokens(&mut contract, application), TokenAmount::ZERO);
+        assert!(ledger(&contract).await.is_empty());
+    }
+
+    #[test]
+    fn handicap_spreads_must_be_negatable() {
+        let teams = || vec!["Team A".to_string(), "Team B".to_string()];
+        assert!(market_options(MarketKind::Handicap, Some(i64::MIN), &[], teams()).is_err());
+        let labels = market_options(MarketKind::Handicap, Some(-3500), &[], teams()).unwrap();
+        assert_eq!(labels, ["Team A -3.5", "Team B +3.5"]);
+    }
+}
@@ -6,8 +6,13 @@
 //! This module defines the operations and messages that can be sent to the contract,
 //! as well as the GraphQL query interface for the service.
 
+use std::collections::BTreeMap;
+
 use async_graphql::{Request, Response};
-use linera_sdk::linera_base_types::{ContractAbi, ServiceAbi};
+use linera_sdk::{
+    abis::fungible::FungibleTokenAbi,
+    linera_base_types::{AccountOwner, ApplicationId, ContractAbi, ServiceAbi},
+};
 use serde::{Deserialize, Serialize};
 
 /// The main ABI struct for the Live Play Predictor application.
@@ -19,23 +24,116 @@ pub type MarketId = u64;
 /// Unique identifier for a user's bet.
 pub type BetId = u64;
 
+/// Unique identifier for a user's parlay.
+pub type ParlayId = u64;
+
 /// Amount in tokens (with 6 decimal precision).
 pub type Amount = u128;
 
-/// Timestamp in milliseconds since Unix epoch.
-pub type Timestamp = u64;
+/// Code of the currency a market and balances are denominated in (e.g., "USDC").
+pub type Currency = String;
+
+/// Currency of applications backed by the chain's native token.
+pub const NATIVE_CURRENCY: &str = "NATIVE";
+
+/// Block timestamp in microseconds since Unix epoch.
+pub use linera_sdk::linera_base_types::Timestamp;
+
+/// Application parameters, fixed when the application is created.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LivePredictParameters {
+    /// Owners allowed to create markets, manage the lifecycle of any market
+    /// and grant or revoke roles.
+    pub admins: Vec<AccountOwner>,
+    /// Fungible token applications backing user balances by currency, if instantiated
+    /// with `Backing::Fungible`.
+    #[serde(default)]
+    pub tokens: BTreeMap<Currency, ApplicationId<FungibleTokenAbi>>,
+}
+
+/// Arguments the application is instantiated with.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct InstantiationArgument {
+    /// Protocol fee rate in basis points (e.g., 100 = 1%, at most 5%).
+    pub fee_rate_bps: u32,
+    /// Tokens backing user balances.
+    #[serde(default)]
+    pub backing: Backing,
+}
+
+/// Tokens backing user balances, moved in and out by deposits and withdrawals.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, async_graphql::Enum,
+)]
+pub enum Backing {
+    /// Tokens of the fungible token applications set in the parameters, one per
+    /// currency.
+    #[default]
+    Fungible,
+    /// The chain's native token, as the single currency `NATIVE_CURRENCY`.
+    Native,
+}
+
+/// Roles that admins can grant to owners.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, async_graphql::Enum)]
+pub enum Role {
+    /// Can create markets and manage the lifecycle of their own markets.
+    MarketCreator,
+    /// Can lock and resolve any market (oracle).
+    Resolver,
+    /// Can suspend, resume, lock and cancel any market, and void its options.
+    RiskManager,
+    /// Can withdraw accumulated protocol fees.
+    TreasuryManager,
+}
+
+/// How a market prices bets and settles payouts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, async_graphql::Enum)]
+pub enum PricingMode {
+    /// Winners share the final pool, odds at bet time are indicative.
+    Parimutuel,
+    /// Odds are guaranteed at bet time and payouts are backed by house liquidity.
+    FixedOdds,
+    /// Prices follow a logarithmic market scoring rule, bets buy shares paying one
+    /// unit each if the option wins, backed by house liquidity.
+    Lmsr,
+}
+
+/// Shape of a market's options and how it is resolved.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, async_graphql::Enum)]
+pub enum MarketKind {
+    /// Options are free-form labels, resolved by naming the winning option.
+    Categorical,
+    /// "Over" and "Under" options on a numeric line, resolved from the actual value.
+    /// A value landing exactly on the line is a push and all stakes are refunded.
+    OverUnder,
+    /// Team A and team B with a spread (line) applied to team A's score, resolved from
+    /// the final score. A margin landing exactly on the spread is a push and all
+    /// stakes are refunded.
+    Handicap,
+    /// One option per range between bucket boundaries, resolved from the actual value.
+    Range,
+    /// "Long" and "Short" options between a lower and upper bound, resolved from the
+    /// actual value. Long pays in proportion to how far the value lands between the
+    /// bounds and Short pays the rest.
+    Scalar,
+}
 
 /// Represents the status of a betting market.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, async_graphql::Enum)]
 pub enum MarketStatus {
     /// Market is open for betting.
     Open,
+    /// Market is temporarily suspended, no bets accepted until resumed.
+    Suspended,
     /// Market is locked, no more bets accepted.
     Locked,
     /// Market has been resolved with an outcome.
     Resolved,
     /// Market was cancelled, all bets refunded.
     Cancelled,
+    /// Result landed exactly on the line, all bets refunded.
+    Pushed,
 }
 
 /// Represents a betting option within a market.
@@ -47,6 +145,72 @@ pub struct MarketOption {
     pub label: String,
     /// Total amount bet on this option.
     pub pool: Amount,
+    /// Odds offered on this option (scaled by 1000, fixed-odds markets only).
+    pub odds: u32,
+    /// Total gross payout owed if this option wins, i.e. outstanding shares
+    /// (fixed-odds and LMSR markets only).
+    pub liability: Amount,
+    /// Whether the option was voided, its bets refunded and no more accepted.
+    pub voided: bool,
+}
+
+/// A winning option of a resolved market and the share of a full win it pays.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, async_graphql::SimpleObject)]
+pub struct WinningOption {
+    /// Option identifier.
+    pub option_id: u8,
+    /// Share of a full win paid on this option, in basis points (10000 = full win,
+    /// 5000 = half under a two-way dead heat).
+    pub weight: u32,
+}
+
+/// Limits on the stakes a market accepts. Unset limits fall back to the global
+/// limits, and unset global limits don't apply.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StakeLimits {
+    /// Largest single stake.
+    pub max_stake: Option<Amount>,
+    /// Largest total an owner can stake on a market.
+    pub max_owner_stake: Option<Amount>,
+    /// Largest total pool of a market.
+    pub max_pool: Option<Amount>,
+}
+
+/// Bounds and default of the odds a market quotes (scaled by 1000).
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, async_graphql::SimpleObject,
+    async_graphql::InputObject,
+)]
+#[graphql(input_name = "OddsConfigInput")]
+pub struct OddsConfig {
+    /// Lowest odds quoted.
+    pub floor: u32,
+    /// Highest odds quoted.
+    pub cap: u32,
+    /// Odds quoted on an empty parimutuel pool, or opening odds of fixed-odds options.
+    /// Not named `default`, which would shadow `Default::default` with its resolver.
+    #[graphql(name = "default")]
+    #[serde(rename = "default")]
+    pub default_odds: u32,
+}
+
+impl Default for OddsConfig {
+    fn default() -> Self {
+        OddsConfig {
+            floor: 1000,
+            cap: 10000,
+            default_odds: 2000,
+        }
+    }
+}
+
+/// Outcome of another market that a conditional market depends on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, async_graphql::SimpleObject)]
+pub struct MarketCondition {
+    /// Parent market.
+    pub market_id: MarketId,
+    /// Option the parent market must resolve to.
+    pub option_id: u8,
 }
 
 /// Represents a betting market (a specific prediction opportunity).
@@ -60,6 +224,31 @@ pub struct Market {
     pub market_type: String,
     /// Human-readable title.
     pub title: String,
+    /// Shape of the options and how the market is resolved.
+    pub kind: MarketKind,
+    /// Numeric line scaled by 1000, e.g. 6500 = 6.5. The total for over/under markets,
+    /// the spread applied to team A for handicap markets.
+    pub line: Option<i64>,
+    /// Team A and team B (handicap markets only).
+    pub teams: Vec<String>,
+    /// Bucket boundaries (range markets) or lower and upper bound (scalar markets),
+    /// scaled by 1000.
+    pub bounds: Vec<i64>,
+    /// Owner who created the market.
+    pub creator: AccountOwner,
+    /// Currency stakes and payouts are denominated in.
+    pub currency: Currency,
+    /// Bounds and default of the odds quoted (parimutuel and fixed-odds markets only).
+    pub odds_config: OddsConfig,
+    /// Stake limits of this market, falling back to the global limits.
+    pub limits: StakeLimits,
+    /// Parent market outcome this market depends on. If the parent settles any other
+    /// way the market is cancelled and refunded.
+    pub condition: Option<MarketCondition>,
+    /// How bets are priced and settled.
+    pub pricing: PricingMode,
+    /// Liquidity parameter `b` (LMSR markets only).
+    pub liquidity: Amount,
     /// Available betting options.
     pub options: Vec<MarketOption>,
     /// Current market status.
@@ -68,8 +257,27 @@ pub struct Market {
     pub created_at: Timestamp,
     /// Timestamp when market locks (no more bets).
     pub locks_at: Timestamp,
-    /// Winning option ID (if resolved).
-    pub winning_option: Option<u8>,
+    /// Winning options and their weights (if resolved). Multi-winner markets have
+    /// several full-weight winners, dead heats split the weight between the tied
+    /// options, and scalar markets split it between Long and Short.
+    pub winners: Vec<WinningOption>,
+    /// Numeric result scaled by 1000 (if resolved by value).
+    pub result: Option<i64>,
+    /// Amount reserved for winning bets (set on resolution). For parimutuel markets
+    /// this is the total pool minus the protocol fee, for fixed-odds markets the
+    /// winning option's liability.
+    pub payout_pool: Amount,
+    /// House liquidity reserved to cover the worst-case payout
+    /// (fixed-odds and LMSR markets only).
+    pub house_reserve: Amount,
+    /// Stakes withheld by cash-outs, added to the payout pool at resolution
+    /// (parimutuel markets only).
+    pub retained: Amount,
+    /// Creator's seed liquidity on each option, empty if unseeded (parimutuel markets
+    /// only). Seeds are counted in the option pools but tracked apart from bets.
+    pub seed: Vec<Amount>,
+    /// Amount returned to the creator for its seed (set on settlement).
+    pub seed_payout: Option<Amount>,
 }
 
 /// Represents a user's bet on a market.
@@ -77,25 +285,165 @@ pub struct Market {
 pub struct Bet {
     /// Unique bet identifier.
     pub id: BetId,
-    /// User's chain ID (owner).
-    pub owner: String,
+    /// Account owner who placed the bet.
+    pub owner: AccountOwner,
     /// Market this bet is for.
     pub market_id: MarketId,
     /// Selected option ID.
     pub option_id: u8,
     /// Amount wagered.
     pub amount: Amount,
-    /// Odds at time of bet (scaled by 1000, e.g., 1500 = 1.5x).
+    /// Indicative odds quoted at time of bet (scaled by 1000, e.g., 1500 = 1.5x).
+    /// The actual payout is computed from the final pools at resolution.
     pub odds: u32,
+    /// Shares held, each paying one unit if the option wins
+    /// (fixed-odds and LMSR markets only).
+    pub shares: Amount,
     /// Timestamp when bet was placed.
     pub placed_at: Timestamp,
     /// Whether bet has been settled.
     pub settled: bool,
+    /// Whether bet was cashed out before the market locked.
+    pub cashed_out: bool,
+    /// Bet this record was split from by a partial cash-out.
+    pub split_from: Option<BetId>,
     /// Payout amount (if won and settled).
     pub payout: Option<Amount>,
 }
 
+/// Outcome of a parlay leg, or of a whole parlay.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, async_graphql::Enum)]
+pub enum LegStatus {
+    /// Market not settled yet.
+    Pending,
+    /// Selected option won.
+    Won,
+    /// Selected option lost.
+    Lost,
+    /// Market was cancelled or pushed, the leg drops out of the parlay.
+    Void,
+}
+
+/// A single selection within a parlay.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ParlayLeg {
+    /// Market this leg is for.
+    pub market_id: MarketId,
+    /// Selected option ID.
+    pub option_id: u8,
+    /// Odds of the selection when the parlay was placed (scaled by 1000).
+    pub odds: u32,
+}
+
+/// Represents a user's parlay: a single stake on several selections that only
+/// pays out if every selection wins. Payouts are backed by house liquidity.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Parlay {
+    /// Unique parlay identifier.
+    pub id: ParlayId,
+    /// Account owner who placed the parlay.
+    pub owner: AccountOwner,
+    /// Currency of every leg's market.
+    pub currency: Currency,
+    /// Selections, each on a different market.
+    pub legs: Vec<ParlayLeg>,
+    /// Amount wagered.
+    pub amount: Amount,
+    /// Combined odds of all legs (scaled by 1000).
+    pub odds: u32,
+    /// Gross payout if every leg wins.
+    pub potential_payout: Amount,
+    /// House liquidity reserved to cover the potential payout.
+    pub house_reserve: Amount,
+    /// Timestamp when parlay was placed.
+    pub placed_at: Timestamp,
+    /// Whether parlay has been settled.
+    pub settled: bool,
+    /// Payout amount (if settled).
+    pub payout: Option<Amount>,
+}
+
+/// Why funds moved between two ledger accounts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, async_graphql::Enum)]
+pub enum LedgerReason {
+    /// Tokens deposited from the owner's token account.
+    Deposit,
+    /// Tokens withdrawn to the owner's token account.
+    Withdrawal,
+    /// Stake placed on a bet.
+    Stake,
+    /// Stake placed on a parlay.
+    ParlayStake,
+    /// Stake returned by a cancelled or pushed market or a voided option.
+    Refund,
+    /// Winnings of a bet after fees, or its stake if nobody backed a winner.
+    Payout,
+    /// Winnings of a parlay after fees, or its stake if every leg is void.
+    ParlayPayout,
+    /// Value of a bet sold back while its market is open, after fees.
+    CashOut,
+    /// Protocol fee taken from a pool, payout or cash-out.
+    Fee,
+    /// Seed liquidity put into a market by its creator.
+    Seed,
+    /// Seed liquidity returned to the creator with its winnings.
+    SeedReturn,
+    /// Funds moved into the house liquidity pool.
+    HouseFunding,
+    /// Funds moved out of the house liquidity pool.
+    HouseWithdrawal,
+    /// House liquidity reserved for the worst case of a market or parlay.
+    HouseReserve,
+    /// Unused reserve and stakes won by the house returned to its liquidity pool.
+    HouseReturn,
+    /// Protocol fees withdrawn by a treasury manager.
+    FeeWithdrawal,
+}
+
+/// An account of the double-entry ledger. Owner balances are the only accounts users
+/// hold, the others hold funds while they are outside any balance.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LedgerAccount {
+    /// An owner's available balance.
+    Owner(AccountOwner),
+    /// An owner's token account outside the application.
+    External(AccountOwner),
+    /// Accumulated protocol fees.
+    Protocol,
+    /// The house liquidity pool.
+    House,
+    /// Stakes, seeds and house reserve held by a market.
+    Market(MarketId),
+    /// Stake and house reserve held by a parlay.
+    Parlay(ParlayId),
+}
+
+/// A movement of funds from one ledger account to another, recorded in the
+/// append-only ledger. Every entry debits and credits the same amount, so the
+/// balances of all accounts always add up to the funds the application holds.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LedgerEntry {
+    /// Account the funds are taken from.
+    pub debit: LedgerAccount,
+    /// Account the funds are added to.
+    pub credit: LedgerAccount,
+    /// Currency of the funds.
+    pub currency: Currency,
+    /// Amount moved.
+    pub amount: Amount,
+    /// Why the funds moved.
+    pub reason: LedgerReason,
+    /// Related market, none for parlays and owner, house or protocol transfers.
+    pub market_id: Option<MarketId>,
+    /// Related bet, or parlay for parlay stakes and payouts.
+    pub bet_id: Option<BetId>,
+    /// Timestamp of the movement.
+    pub timestamp: Timestamp,
+}
+
 /// Operations that can be executed on the contract.
+// Operations are deserialized once per execution, so their size doesn't matter
+#[allow(clippy::large_enum_variant)]
 #[derive(Debug, Deserialize, Serialize)]
 pub enum Operation {
     /// Create a new betting market.
@@ -103,8 +451,32 @@ pub enum Operation {
         match_id: String,
         market_type: String,
         title: String,
+        /// Option labels, empty for numeric markets which generate their own, or
+        /// team A and team B for handicap markets.
         options: Vec<String>,
         locks_at: Timestamp,
+        kind: MarketKind,
+        /// Numeric line for over/under markets, or spread applied to team A for
+        /// handicap markets (scaled by 1000).
+        line: Option<i64>,
+        /// Bucket boundaries for range markets, or lower and upper bound for scalar
+        /// markets (scaled by 1000).
+        bounds: Vec<i64>,
+        pricing: PricingMode,
+        /// Liquidity parameter `b` for LMSR markets, zero otherwise.
+        liquidity: Amount,
+        /// Parent market and option this market is conditional on.
+        condition: Option<(MarketId, u8)>,
+        /// Stake limits of the market, unset limits falling back to the global ones.
+        limits: StakeLimits,
+        /// Bounds and default of the odds quoted, 1x-10x defaulting to 2x if omitted.
+        /// Fixed-odds options open at the default odds if given.
+        odds_config: Option<OddsConfig>,
+        /// Seed liquidity per option debited from the creator's balance, empty for none
+        /// (parimutuel markets only).
+        seed: Vec<Amount>,
+        /// Currency stakes and payouts are denominated in.
+        currency: Currency,
     },
     
     /// Place a bet on a market option.
@@ -112,6 +484,8 @@ pub enum Operation {
         market_id: MarketId,
         option_id: u8,
         amount: Amount,
+        /// Reject the bet if its odds are below this (scaled by 1000).
+        min_odds: Option<u32>,
     },
     
     /// Lock a market (stop accepting bets).
@@ -119,12 +493,39 @@ pub enum Operation {
         market_id: MarketId,
     },
     
-    /// Resolve a market with the winning option.
+    /// Resolve a categorical market with the winning option.
     ResolveMarket {
         market_id: MarketId,
         winning_option: u8,
     },
     
+    /// Resolve a categorical market with several winning options, each with an
+    /// optional weight in basis points (full win if omitted).
+    ResolveWinners {
+        market_id: MarketId,
+        winners: Vec<(u8, Option<u32>)>,
+    },
+    
+    /// Resolve an over/under, range or scalar market from its numeric result (scaled
+    /// by 1000).
+    ResolveScalar {
+        market_id: MarketId,
+        value: i64,
+    },
+    
+    /// Void a single option, refunding every bet on it.
+    VoidOption {
+        market_id: MarketId,
+        option_id: u8,
+    },
+    
+    /// Resolve a handicap market from the final score.
+    ResolveScore {
+        market_id: MarketId,
+        score_a: u32,
+        score_b: u32,
+    },
+    
     /// Cancel a market and refund all bets.
     CancelMarket {
         market_id: MarketId,
@@ -135,13 +536,93 @@ pub enum Operation {
         bet_id: BetId,
     },
     
-    /// Deposit tokens to user balance.
+    /// Sell a bet back at its current value while the market is open.
+    CashOut {
+        bet_id: BetId,
+        /// Reject the cash-out if the value after fees is below this.
+        min_value: Amount,
+    },
+    
+    /// Sell part of a bet back at its current value while the market is open.
+    PartialCashOut {
+        bet_id: BetId,
+        /// Fraction of the stake to cash out, in basis points.
+        fraction_bps: u32,
+        /// Reject the cash-out if the value after fees is below this.
+        min_value: Amount,
+    },
+    
+    /// Place a single stake on several market options, each on a different market.
+    PlaceParlay {
+        legs: Vec<(MarketId, u8)>,
+        amount: Amount,
+    },
+    
+    /// Settle a parlay once all its legs are settled or any leg lost.
+    SettleParlay {
+        parlay_id: ParlayId,
+    },
+    
+    /// Suspend betting on an open market.
+    SuspendMarket {
+        market_id: MarketId,
+    },
+    
+    /// Resume betting on a suspended market.
+    ResumeMarket {
+        market_id: MarketId,
+    },
+    
+    /// Set the odds offered on each option of a fixed-odds market.
+    SetOdds {
+        market_id: MarketId,
+        odds: Vec<u32>,
+    },
+    
+    /// Move funds from the caller's balance into the house liquidity pool.
+    FundHouse {
+        currency: Currency,
+        amount: Amount,
+    },
+    
+    /// Move unreserved house liquidity to the caller's balance.
+    WithdrawHouse {
+        currency: Currency,
+        amount: Amount,
+    },
+    
+    /// Set the global stake limits applying to markets without their own.
+    SetStakeLimits {
+        limits: StakeLimits,
+    },
+    
+    /// Grant a role to an owner.
+    GrantRole {
+        owner: AccountOwner,
+        role: Role,
+    },
+    
+    /// Revoke a role from an owner.
+    RevokeRole {
+        owner: AccountOwner,
+        role: Role,
+    },
+    
+    /// Move accumulated protocol fees to the caller's balance.
+    WithdrawFees {
+        currency: Currency,
+        amount: Amount,
+    },
+    
+    /// Deposit tokens to user balance, transferred from the signer's account.
     Deposit {
+        currency: Currency,
         amount: Amount,
     },
     
-    /// Withdraw tokens from user balance.
+    /// Withdraw tokens from user balance, transferred back to the signer's account.
     Withdraw {
+        currency: Currency,
         amount: Amount,
     },
 }
@@ -159,12 +640,59 @@ pub enum OperationResponse {
     MarketResolved { market_id: MarketId, winning_option: u8 },
     /// Market cancelled.
     MarketCancelled { market_id: MarketId },
+    /// Option voided and its bets refunded.
+    OptionVoided { market_id: MarketId, option_id: u8, refunded: Amount },
+    /// Market resolved with several winning options.
+    WinnersResolved { market_id: MarketId, winners: Vec<WinningOption> },
+    /// Scalar market resolved, `long_weight_bps` of the payouts go to Long.
+    ScalarResolved { market_id: MarketId, value: i64, long_weight_bps: u32 },
+    /// Market result landed on the line, all bets refunded.
+    MarketPushed { market_id: MarketId, value: i64 },
+    /// Market suspended.
+    MarketSuspended { market_id: MarketId },
+    /// Market resumed.
+    MarketResumed { market_id: MarketId },
+    /// Fixed odds updated.
+    OddsUpdated { market_id: MarketId },
+    /// House liquidity funded.
+    HouseFunded { amount: Amount, liquidity: Amount },
+    /// House liquidity withdrawn.
+    HouseWithdrawn { amount: Amount, liquidity: Amount },
+    /// Global stake limits updated.
+    StakeLimitsUpdated { limits: StakeLimits },
+    /// Role granted.
+    RoleGranted { owner: AccountOwner, role: Role },
+    /// Role revoked.
+    RoleRevoked { owner: AccountOwner, role: Role },
+    /// Protocol fees withdrawn.
+    FeesWithdrawn { amount: Amount, new_balance: Amount },
+    /// Bet cashed out.
+    CashedOut { bet_id: BetId, amount: Amount },
+    /// Part of a bet cashed out, split off into a settled bet record.
+    PartiallyCashedOut {
+        bet_id: BetId,
+        exit_bet_id: BetId,
+        amount: Amount,
+        remaining: Amount,
+    },
+    /// Parlay placed successfully.
+    ParlayPlaced { parlay_id: ParlayId, odds: u32 },
+    /// Parlay settled.
+    ParlaySettled { parlay_id: ParlayId, amount: Amount },
+    /// Cash-out rejected because its value after fees is below the caller's minimum.
+    CashOutBelowMinimum { value: Amount, min_value: Amount },
     /// Winnings claimed.
     WinningsClaimed { bet_id: BetId, amount: Amount },
     /// Deposit successful.
     Deposited { amount: Amount, new_balance: Amount },
     /// Withdrawal successful.
     Withdrawn { amount: Amount, new_balance: Amount },
+    /// Bet rejected because it would exceed one of the market's stake limits.
+    StakeLimitExceeded { limit: Amount, message: String },
+    /// Bet rejected because its odds moved below the caller's minimum.
+    SlippageExceeded { odds: u32, min_odds: u32 },
+    /// Caller is not allowed to perform the operation.
+    Unauthorized { message: String },
     /// Operation failed.
     Error { message: String },
 }
@@ -173,9 +701,7 @@ pub enum OperationResponse {
 #[derive(Debug, Deserialize, Serialize)]
 pub enum Message {
     /// Sync market state to another chain.
-    SyncMarket { market: Market },
-    /// Notify about market resolution.
-    MarketResolved { market_id: MarketId, winning_option: u8 },
+    SyncMarket { market: Box<Market> },
 }
 
 impl ContractAbi for LivePredictAbi {
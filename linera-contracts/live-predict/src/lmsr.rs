@@ -0,0 +1,287 @@
+// Copyright (c) Live Play Predictor
+// SPDX-License-Identifier: Apache-2.0
+
+//! Logarithmic market scoring rule (LMSR) pricing.
+//!
+//! The cost function is `C(q) = b * ln(sum(exp(q_i / b)))`, where `q_i` is the number
+//! of outstanding shares of option `i` and `b` is the liquidity parameter. All math
+//! uses 12-decimal fixed-point integers so results are deterministic in wasm.
+
+use live_predict::Amount;
+
+/// Fixed-point representation of 1.
+const ONE: i128 = 1_000_000_000_000;
+
+/// Fixed-point representation of ln(2).
+const LN_2: i128 = 693_147_180_560;
+
+/// Largest exponent accepted by `exp`, keeping results well within `i128`.
+const MAX_EXP: i128 = 40 * ONE;
+
+/// Scale of the prices returned by `prices` (1_000_000 = probability of 1).
+pub const PRICE_SCALE: u32 = 1_000_000;
+
+/// Multiply two fixed-point numbers.
+fn mul(a: i128, b: i128) -> Option<i128> {
+    a.checked_mul(b).map(|product| product / ONE)
+}
+
+/// Divide two fixed-point numbers.
+fn div(a: i128, b: i128) -> Option<i128> {
+    if b == 0 {
+        return None;
+    }
+    a.checked_mul(ONE).map(|product| product / b)
+}
+
+/// Convert the ratio of two amounts to a fixed-point number.
+fn ratio(a: Amount, b: Amount) -> Option<i128> {
+    if b == 0 {
+        return None;
+    }
+    let value = a.checked_mul(ONE as u128)? / b;
+    i128::try_from(value).ok()
+}
+
+/// Scale an amount by a non-negative fixed-point factor, rounding down.
+fn scale(amount: Amount, factor: i128) -> Option<Amount> {
+    let factor = u128::try_from(factor).ok()?;
+    amount.checked_mul(factor).map(|product| product / ONE as u128)
+}
+
+/// Compute `e^x` for a fixed-point `x`.
+fn exp(x: i128) -> Option<i128> {
+    if x > MAX_EXP {
+        return None;
+    }
+    if x < -MAX_EXP {
+        return Some(0);
+    }
+
+    // Reduce to e^x = 2^k * e^r with 0 <= r < ln(2)
+    let k = x.div_euclid(LN_2);
+    let r = x.rem_euclid(LN_2);
+
+    // Taylor series for e^r
+    let mut sum = ONE;
+    let mut term = ONE;
+    let mut n = 1;
+    while term != 0 {
+        term = mul(term, r)? / n;
+        sum += term;
+        n += 1;
+    }
+
+    if k >= 0 {
+        sum.checked_mul(1 << k)
+    } else {
+        Some(sum >> -k)
+    }
+}
+
+/// Compute `ln(x)` for a positive fixed-point `x`.
+fn ln(x: i128) -> Option<i128> {
+    if x <= 0 {
+        return None;
+    }
+
+    // Reduce to ln(x) = k * ln(2) + ln(m) with 1 <= m < 2
+    let mut k = 0;
+    let mut m = x;
+    while m >= 2 * ONE {
+        m >>= 1;
+        k += 1;
+    }
+    while m < ONE {
+        m <<= 1;
+        k -= 1;
+    }
+
+    // ln(m) = 2 * atanh(z) with z = (m - 1) / (m + 1)
+    let z = div(m - ONE, m + ONE)?;
+    let z2 = mul(z, z)?;
+    let mut sum = 0;
+    let mut term = z;
+    let mut n = 1;
+    while term != 0 {
+        sum += term / n;
+        term = mul(term, z2)?;
+        n += 2;
+    }
+
+    Some(k * LN_2 + 2 * sum)
+}
+
+/// Current fixed-point price (probability) of each option.
+fn fixed_prices(shares: &[Amount], liquidity: Amount) -> Option<Vec<i128>> {
+    // Shift by the largest position so every exponent is <= 0
+    let max_shares = shares.iter().copied().max()?;
+    let weights = shares
+        .iter()
+        .map(|&q| exp(-ratio(max_shares - q, liquidity)?))
+        .collect::<Option<Vec<_>>>()?;
+    let total: i128 = weights.iter().sum();
+    weights.into_iter().map(|w| div(w, total)).collect()
+}
+
+/// Split the cost function into the largest position and a fixed-point log term,
+/// `C(q) = max(q) + b * ln(sum(exp((q_i - max(q)) / b)))`, which keeps the logarithm's
+/// argument between 1 and the number of options.
+fn cost_parts(shares: &[Amount], liquidity: Amount) -> Option<(Amount, i128)> {
+    let max_shares = shares.iter().copied().max()?;
+    let total =
+        shares.iter().map(|&q| exp(-ratio(max_shares - q, liquidity)?)).sum::<Option<i128>>()?;
+    Some((max_shares, ln(total)?))
+}
+
+/// Current price of each option, scaled by `PRICE_SCALE`.
+pub fn prices(shares: &[Amount], liquidity: Amount) -> Option<Vec<u32>> {
+    fixed_prices(shares, liquidity)?
+        .into_iter()
+        .map(|p| u32::try_from(p * PRICE_SCALE as i128 / ONE).ok())
+        .collect()
+}
+
+/// Number of shares of `option` that `cost` buys, rounded down.
+///
+/// Solving `C(q + d * e_i) - C(q) = cost` gives
+/// `d = b * (ln(p_i + e^(cost / b) - 1) - ln(p_i))`, where `ln(p_i)` is taken from the
+/// split cost function so it stays precise on cheap options.
+pub fn shares_for_cost(
+    shares: &[Amount],
+    liquidity: Amount,
+    option: usize,
+    cost: Amount,
+) -> Option<Amount> {
+    let (max_shares, log_total) = cost_parts(shares, liquidity)?;
+    let log_price = -ratio(max_shares - shares.get(option)?, liquidity)? - log_total;
+    let growth = exp(ratio(cost, liquidity)?)? - ONE;
+    let delta = ln(exp(log_price)? + growth)? - log_price;
+    scale(liquidity, delta.max(0))
+}
+
+/// Amount paid for selling `sold` shares of `option` back to the market maker,
+/// rounded down past the fixed-point error of the logarithms.
+///
+/// Computed as `C(q) - C(q - d * e_i)` from the split cost function, since the closed
+/// form `-b * ln(1 - p_i * (1 - e^(-d / b)))` loses precision on cheap options.
+pub fn sale_proceeds(
+    shares: &[Amount],
+    liquidity: Amount,
+    option: usize,
+    sold: Amount,
+) -> Option<Amount> {
+    let mut remaining = shares.to_vec();
+    let held = remaining.get_mut(option)?;
+    *held = held.checked_sub(sold)?;
+    let (max_before, log_before) = cost_parts(shares, liquidity)?;
+    let (max_after, log_after) = cost_parts(&remaining, liquidity)?;
+    let shift = i128::try_from(max_before.checked_sub(max_after)?).ok()?;
+    let spread =
+        i128::try_from(liquidity).ok()?.checked_mul(log_before - log_after)?.div_euclid(ONE);
+    let error = liquidity / ONE as u128 + 1;
+    Some(u128::try_from(shift + spread).unwrap_or(0).saturating_sub(error))
+}
+
+/// Worst-case loss of a market maker with `options` outcomes, `b * ln(n)`, rounded up.
+pub fn max_loss(liquidity: Amount, options: usize) -> Option<Amount> {
+    let factor = ln((options as i128).checked_mul(ONE)?)?;
+    scale(liquidity, factor).map(|loss| loss + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Fixed-point value of e.
+    const E: i128 = 2_718_281_828_459;
+
+    /// Liquidity parameter used by the tests: 1000 tokens with 6 decimals.
+    const LIQUIDITY: Amount = 1_000_000_000;
+
+    fn assert_close(actual: i128, expected: i128, tolerance: i128) {
+        assert!(
+            (actual - expected).abs() <= tolerance,
+            "{actual} is not within {tolerance} of {expected}"
+        );
+    }
+
+    #[test]
+    fn exp_matches_known_values() {
+        assert_eq!(exp(0), Some(ONE));
+        assert_close(exp(ONE).unwrap(), E, 100);
+        assert_close(exp(-ONE).unwrap(), 367_879_441_171, 100);
+        assert_close(exp(LN_2).unwrap(), 2 * ONE, 100);
+        assert_close(exp(10 * ONE).unwrap(), 22_026_465_794_806_718, 10_000_000);
+        assert_eq!(exp(-MAX_EXP - 1), Some(0));
+        assert_eq!(exp(MAX_EXP + 1), None);
+    }
+
+    #[test]
+    fn ln_matches_known_values() {
+        assert_eq!(ln(ONE), Some(0));
+        assert_close(ln(E).unwrap(), ONE, 100);
+        assert_close(ln(2 * ONE).unwrap(), LN_2, 100);
+        assert_close(ln(ONE / 2).unwrap(), -LN_2, 100);
+        assert_close(ln(10 * ONE).unwrap(), 2_302_585_092_994, 100);
+        assert_eq!(ln(0), None);
+        assert_eq!(ln(-ONE), None);
+    }
+
+    #[test]
+    fn prices_sum_to_one() {
+        let books: [&[Amount]; 4] = [
+            &[0, 0],
+            &[0, 0, 0],
+            &[5_000_000_000, 0, 250_000_000],
+            &[1, 2_000_000_000, 3_000_000_000, 40_000_000_000],
+        ];
+        for shares in books {
+            let prices = prices(shares, LIQUIDITY).unwrap();
+            let total: u32 = prices.iter().sum();
+            let rounding = shares.len() as u32;
+            assert!(total <= PRICE_SCALE && total + rounding >= PRICE_SCALE, "{prices:?}");
+        }
+        assert_eq!(prices(&[0, 0], LIQUIDITY).unwrap(), vec![500_000, 500_000]);
+    }
+
+    #[test]
+    fn selling_never_returns_more_than_was_paid() {
+        let books: [&[Amount]; 4] = [
+            &[0, 0],
+            &[0, 700_000_000, 0],
+            &[9_000_000_000, 0, 3_000_000_000],
+            // The second option trades at about e^-20
+            &[20_000_000_000, 0],
+        ];
+        for shares in books {
+            for option in 0..shares.len() {
+                for cost in [1, 999, 1_000_000, 250_000_000, 5_000_000_000, 20_000_000_000] {
+                    let bought = shares_for_cost(shares, LIQUIDITY, option, cost).unwrap();
+                    let mut after = shares.to_vec();
+                    after[option] += bought;
+                    let proceeds = sale_proceeds(&after, LIQUIDITY, option, bought).unwrap();
+                    assert!(proceeds <= cost, "paid {cost}, sold back for {proceeds}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn max_loss_bounds_the_market_maker_loss() {
+        for options in [2, 3, 5] {
+            let bound = max_loss(LIQUIDITY, options).unwrap();
+            for cost in [1_000_000, 100_000_000, 1_000_000_000, 20_000_000_000] {
+                // Traders repeatedly buy the option that ends up winning
+                let mut shares = vec![0; options];
+                let mut paid = 0;
+                for _ in 0..10 {
+                    shares[0] += shares_for_cost(&shares, LIQUIDITY, 0, cost).unwrap();
+                    paid += cost;
+                }
+                let loss = shares[0].saturating_sub(paid);
+                assert!(loss <= bound, "lost {loss} with a bound of {bound}");
+            }
+        }
+    }
+}
@@ -7,16 +7,34 @@
 
 #![cfg_attr(target_arch = "wasm32", no_main)]
 
+// Shared with the contract, which uses the methods the service doesn't
+#[allow(dead_code)]
+mod lmsr;
+#[allow(dead_code)]
 mod state;
+#[cfg(test)]
+mod test_support;
 
 use std::sync::Arc;
 
-use async_graphql::{Context, EmptySubscription, Object, Request, Response, Schema};
-use linera_sdk::{linera_base_types::WithServiceAbi, views::View, Service, ServiceRuntime};
-use live_predict::{Amount, Bet, BetId, LivePredictAbi, Market, MarketId, MarketStatus, Operation};
+use async_graphql::{EmptySubscription, Object, Request, Response, Schema};
+use linera_sdk::{
+    linera_base_types::{AccountOwner, WithServiceAbi},
+    views::View,
+    Service, ServiceRuntime,
+};
+use live_predict::{
+    Amount, Backing, Bet, BetId, Currency, LedgerAccount, LedgerEntry, LedgerReason, LegStatus,
+    LivePredictAbi, LivePredictParameters, Market, MarketCondition, MarketId, MarketKind,
+    MarketOption, MarketStatus, OddsConfig, Operation, Parlay, ParlayId, PricingMode, Role,
+    StakeLimits, Timestamp, WinningOption,
+};
 
 use self::state::LivePredictState;
 
+/// Largest number of ledger entries returned per page.
+const LEDGER_PAGE_SIZE: u32 = 100;
+
 /// The Live Play Predictor service.
 pub struct LivePredictService {
     state: Arc<LivePredictState>,
@@ -30,7 +48,7 @@ impl WithServiceAbi for LivePredictService {
 }
 
 impl Service for LivePredictService {
-    type Parameters = ();
+    type Parameters = LivePredictParameters;
 
     async fn new(runtime: ServiceRuntime<Self>) -> Self {
         let state = LivePredictState::load(runtime.root_view_storage_context())
@@ -46,6 +64,7 @@ impl Service for LivePredictService {
         let schema = Schema::build(
             QueryRoot {
                 state: self.state.clone(),
+                runtime: self.runtime.clone(),
             },
             MutationRoot {
                 runtime: self.runtime.clone(),
@@ -60,35 +79,70 @@ impl Service for LivePredictService {
 /// GraphQL query root.
 struct QueryRoot {
     state: Arc<LivePredictState>,
+    runtime: Arc<ServiceRuntime<LivePredictService>>,
 }
 
 #[Object]
 impl QueryRoot {
     /// Get a market by ID.
-    async fn market(&self, id: MarketId) -> Option<Market> {
-        self.state.get_market(id).await
+    async fn market(&self, id: MarketId) -> Option<MarketObject> {
+        self.state.get_market(id).await.map(MarketObject::from)
+    }
+
+    /// Get the options of a market with the line each is bet against: the total for
+    /// over/under markets, and each team's spread for handicap markets.
+    async fn option_lines(&self, market_id: MarketId) -> Vec<OptionLine> {
+        let Some(market) = self.state.get_market(market_id).await else {
+            return Vec::new();
+        };
+
+        market
+            .options
+            .iter()
+            .map(|option| OptionLine {
+                option_id: option.id,
+                label: option.label.clone(),
+                line: match (market.kind, market.line) {
+                    (MarketKind::OverUnder, line) => line,
+                    (MarketKind::Handicap, Some(line)) if option.id == 0 => Some(line),
+                    (MarketKind::Handicap, Some(line)) => Some(-line),
+                    _ => None,
+                },
+            })
+            .collect()
+    }
+
+    /// Get the markets conditional on a market's outcome.
+    async fn dependent_markets(&self, market_id: MarketId) -> Vec<MarketObject> {
+        let mut markets = Vec::new();
+        for id in self.state.get_dependents(market_id).await {
+            if let Some(market) = self.state.get_market(id).await {
+                markets.push(market.into());
+            }
+        }
+        markets
     }
 
     /// Get all active (open) markets.
-    async fn active_markets(&self) -> Vec<Market> {
+    async fn active_markets(&self) -> Vec<MarketObject> {
         let market_ids = self.state.active_markets.get().clone();
         let mut markets = Vec::new();
         for id in market_ids {
             if let Some(market) = self.state.get_market(id).await {
-                markets.push(market);
+                markets.push(market.into());
             }
         }
         markets
     }
 
     /// Get markets by match ID.
-    async fn markets_by_match(&self, match_id: String) -> Vec<Market> {
+    async fn markets_by_match(&self, match_id: String) -> Vec<MarketObject> {
         let market_ids = self.state.active_markets.get().clone();
         let mut markets = Vec::new();
         for id in market_ids {
             if let Some(market) = self.state.get_market(id).await {
                 if market.match_id == match_id {
-                    markets.push(market);
+                    markets.push(market.into());
                 }
             }
         }
@@ -96,22 +150,97 @@ impl QueryRoot {
     }
 
     /// Get a bet by ID.
-    async fn bet(&self, id: BetId) -> Option<Bet> {
-        self.state.get_bet(id).await
+    async fn bet(&self, id: BetId) -> Option<BetObject> {
+        self.state.get_bet(id).await.map(BetObject::from)
+    }
+
+    /// Get user balance in a currency.
+    async fn balance(&self, owner: AccountOwner, currency: Currency) -> TokenAmount {
+        self.state.get_balance(&owner, &currency).await.into()
+    }
+
+    /// Get the tokens backing user balances.
+    async fn backing(&self) -> Backing {
+        *self.state.backing.get()
+    }
+
+    /// Get the creator's seed liquidity on a market, per option.
+    async fn market_seed(&self, market_id: MarketId) -> Vec<SeedLiquidity> {
+        let Some(market) = self.state.get_market(market_id).await else {
+            return Vec::new();
+        };
+        market
+            .seed
+            .iter()
+            .zip(&market.options)
+            .map(|(&amount, option)| SeedLiquidity {
+                option_id: option.id,
+                amount: amount.into(),
+                pool: option.pool.into(),
+            })
+            .collect()
+    }
+
+    /// Get the stake limits applying to a market, or the global limits if no market
+    /// is given.
+    async fn stake_limits(&self, market_id: Option<MarketId>) -> Option<StakeLimitsObject> {
+        match market_id {
+            Some(market_id) => {
+                let market = self.state.get_market(market_id).await?;
+                Some(self.state.market_limits(&market).into())
+            }
+            None => Some((*self.state.stake_limits.get()).into()),
+        }
     }
 
-    /// Get user balance.
-    async fn balance(&self, owner: String) -> Amount {
-        self.state.get_balance(&owner).await
+    /// Get the total an owner has staked on a market.
+    async fn owner_stake(&self, market_id: MarketId, owner: AccountOwner) -> TokenAmount {
+        self.state.get_owner_stake(market_id, &owner).await.into()
     }
 
     /// Get user's bets.
-    async fn user_bets(&self, owner: String) -> Vec<Bet> {
+    async fn user_bets(&self, owner: AccountOwner) -> Vec<BetObject> {
         if let Ok(Some(bet_ids)) = self.state.user_bets.get(&owner).await {
             let mut bets = Vec::new();
             for id in bet_ids {
                 if let Some(bet) = self.state.get_bet(id).await {
-                    bets.push(bet);
+                    bets.push(bet.into());
+                }
+            }
+            bets
+        } else {
+            Vec::new()
+        }
+    }
+
+    /// Get a parlay by ID, with the live status of its legs.
+    async fn parlay(&self, id: ParlayId) -> Option<ParlayStatus> {
+        let parlay = self.state.get_parlay(id).await?;
+        Some(self.parlay_status(parlay).await)
+    }
+
+    /// Get user's parlays, with the live status of their legs.
+    async fn user_parlays(&self, owner: AccountOwner) -> Vec<ParlayStatus> {
+        if let Ok(Some(parlay_ids)) = self.state.user_parlays.get(&owner).await {
+            let mut parlays = Vec::new();
+            for id in parlay_ids {
+                if let Some(parlay) = self.state.get_parlay(id).await {
+                    parlays.push(self.parlay_status(parlay).await);
+                }
+            }
+            parlays
+        } else {
+            Vec::new()
+        }
+    }
+
+    /// Get the partial cash-outs split from a bet.
+    async fn partial_cash_outs(&self, bet_id: BetId) -> Vec<BetObject> {
+        if let Ok(Some(bet_ids)) = self.state.bet_splits.get(&bet_id).await {
+            let mut bets = Vec::new();
+            for id in bet_ids {
+                if let Some(bet) = self.state.get_bet(id).await {
+                    bets.push(bet.into());
                 }
             }
             bets
@@ -121,12 +250,12 @@ impl QueryRoot {
     }
 
     /// Get all bets for a market.
-    async fn market_bets(&self, market_id: MarketId) -> Vec<Bet> {
+    async fn market_bets(&self, market_id: MarketId) -> Vec<BetObject> {
         if let Ok(Some(bet_ids)) = self.state.market_bets.get(&market_id).await {
             let mut bets = Vec::new();
             for id in bet_ids {
                 if let Some(bet) = self.state.get_bet(id).await {
-                    bets.push(bet);
+                    bets.push(bet.into());
                 }
             }
             bets
@@ -135,9 +264,54 @@ impl QueryRoot {
         }
     }
 
-    /// Get total betting volume.
-    async fn total_volume(&self) -> Amount {
-        *self.state.total_volume.get()
+    /// Get the roles granted to an owner.
+    async fn roles(&self, owner: AccountOwner) -> Vec<Role> {
+        self.state.get_roles(&owner).await
+    }
+
+    /// Get an owner's available balance, stake locked in unsettled bets, parlays and
+    /// market seeds, and their total, in a currency.
+    async fn balance_breakdown(&self, owner: AccountOwner, currency: Currency) -> BalanceBreakdown {
+        let available = self.state.get_balance(&owner, &currency).await;
+        let locked = self.state.get_locked(&owner, &currency).await;
+        BalanceBreakdown {
+            available: available.into(),
+            locked: locked.into(),
+            total: (available + locked).into(),
+        }
+    }
+
+    /// Get a page of an owner's ledger entries, oldest first, starting at `cursor`
+    /// (0 if omitted). Pass the returned `nextCursor` to get the next page.
+    async fn ledger(
+        &self,
+        owner: AccountOwner,
+        cursor: Option<u64>,
+        limit: Option<u32>,
+    ) -> LedgerPage {
+        self.ledger_page(LedgerAccount::Owner(owner), cursor, limit).await
+    }
+
+    /// Get a page of any ledger account's entries, such as the protocol fees, the house
+    /// or a market, oldest first. Returns an empty page for an incomplete account.
+    async fn account_ledger(
+        &self,
+        account: LedgerAccountObject,
+        cursor: Option<u64>,
+        limit: Option<u32>,
+    ) -> LedgerPage {
+        match account.account() {
+            Some(account) => self.ledger_page(account, cursor, limit).await,
+            None => LedgerPage {
+                entries: Vec::new(),
+                next_cursor: None,
+            },
+        }
+    }
+
+    /// Get total betting volume in a currency.
+    async fn total_volume(&self, currency: Currency) -> TokenAmount {
+        self.state.get_total_volume(&currency).await.into()
     }
 
     /// Get protocol fee rate (basis points).
@@ -145,9 +319,109 @@ impl QueryRoot {
         *self.state.fee_rate_bps.get()
     }
 
-    /// Get accumulated protocol fees.
-    async fn protocol_fees(&self) -> Amount {
-        *self.state.protocol_fees.get()
+    /// Get accumulated protocol fees in a currency.
+    async fn protocol_fees(&self, currency: Currency) -> TokenAmount {
+        self.state.get_protocol_fees(&currency).await.into()
+    }
+
+    /// Get the current price of every option in a market.
+    /// LMSR markets report the market maker's prices, other markets the prices
+    /// implied by their current odds.
+    async fn prices(&self, market_id: MarketId) -> Option<Vec<OptionPrice>> {
+        let market = self.state.get_market(market_id).await?;
+
+        let quotes: Vec<(u32, u32)> = match market.pricing {
+            PricingMode::Parimutuel => {
+                let total_pool: Amount = market.options.iter().map(|o| o.pool).sum();
+                market
+                    .options
+                    .iter()
+                    .map(|o| {
+                        if o.voided {
+                            return (0, 0);
+                        }
+                        let odds = LivePredictState::calculate_odds(
+                            &market.odds_config,
+                            total_pool,
+                            o.pool,
+                        );
+                        (invert_price(odds), odds)
+                    })
+                    .collect()
+            }
+            PricingMode::FixedOdds => market
+                .options
+                .iter()
+                .map(|o| (invert_price(o.odds), o.odds))
+                .collect(),
+            PricingMode::Lmsr => {
+                // Voided options drop out of the book and are quoted at zero
+                let outstanding: Vec<Amount> = market
+                    .options
+                    .iter()
+                    .filter(|o| !o.voided)
+                    .map(|o| o.liability)
+                    .collect();
+                let mut prices = lmsr::prices(&outstanding, market.liquidity)?.into_iter();
+                market
+                    .options
+                    .iter()
+                    .map(|o| {
+                        if o.voided {
+                            (0, 0)
+                        } else {
+                            prices.next().map_or((0, 0), |p| (p, invert_price(p)))
+                        }
+                    })
+                    .collect()
+            }
+        };
+
+        Some(
+            quotes
+                .into_iter()
+                .enumerate()
+                .map(|(i, (price, odds))| OptionPrice {
+                    option_id: i as u8,
+                    price,
+                    odds,
+                })
+                .collect(),
+        )
+    }
+
+    /// Get the current cash-out value of a bet.
+    async fn cash_out_quote(&self, bet_id: BetId) -> Option<CashOutQuote> {
+        let bet = self.state.get_bet(bet_id).await?;
+        if bet.settled {
+            return None;
+        }
+
+        // The contract refuses cash-outs once the market's lock time has passed
+        let market = self.state.get_market(bet.market_id).await?;
+        if market.status != MarketStatus::Open || self.runtime.system_time() >= market.locks_at {
+            return None;
+        }
+
+        let value = LivePredictState::cash_out_value(&market, &bet)?;
+        let fee_rate = *self.state.fee_rate_bps.get();
+        let fee = LivePredictState::calculate_fee(value, fee_rate);
+
+        Some(CashOutQuote {
+            value: value.into(),
+            fee: fee.into(),
+            payout: (value - fee).into(),
+        })
+    }
+
+    /// Get house liquidity and profit and loss from house-backed markets in a currency.
+    async fn house(&self, currency: Currency) -> HouseSummary {
+        let (profit, loss) = self.state.get_house_result(&currency).await;
+        HouseSummary {
+            liquidity: self.state.get_house_liquidity(&currency).await.into(),
+            profit: profit.into(),
+            loss: loss.into(),
+        }
     }
 
     /// Calculate potential payout for a hypothetical bet.
@@ -155,40 +429,584 @@ impl QueryRoot {
         &self,
         market_id: MarketId,
         option_id: u8,
-        amount: Amount,
+        amount: TokenAmount,
     ) -> Option<PotentialPayout> {
+        let amount = amount.0;
         let market = self.state.get_market(market_id).await?;
         
         if market.status != MarketStatus::Open {
             return None;
         }
 
-        let option = market.options.get(option_id as usize)?;
+        let option = market.options.get(option_id as usize).filter(|o| !o.voided)?;
         let total_pool: Amount = market.options.iter().map(|o| o.pool).sum();
-        
-        let odds = LivePredictState::calculate_odds(total_pool + amount, option.pool + amount);
         let fee_rate = *self.state.fee_rate_bps.get();
-        let payout = LivePredictState::calculate_payout(amount, odds, fee_rate);
+
+        let (odds, payout) = match market.pricing {
+            PricingMode::Parimutuel => {
+                let odds = LivePredictState::calculate_odds(
+                    &market.odds_config,
+                    total_pool + amount,
+                    option.pool + amount,
+                );
+                // Estimate assuming the pools don't move before resolution
+                let fee = LivePredictState::calculate_fee(total_pool + amount, fee_rate);
+                let payout = LivePredictState::calculate_parimutuel_payout(
+                    amount,
+                    option.pool + amount,
+                    total_pool + amount - fee,
+                );
+                (odds, payout)
+            }
+            PricingMode::FixedOdds => {
+                let gross = LivePredictState::calculate_fixed_payout(amount, option.odds);
+                let fee = LivePredictState::calculate_fee(gross, fee_rate);
+                (option.odds, gross - fee)
+            }
+            PricingMode::Lmsr => {
+                let (outstanding, position) = LivePredictState::lmsr_book(&market, option_id)?;
+                let shares =
+                    lmsr::shares_for_cost(&outstanding, market.liquidity, position, amount)?;
+                let odds = u32::try_from(shares * 1000 / amount.max(1)).unwrap_or(u32::MAX);
+                let fee = LivePredictState::calculate_fee(shares, fee_rate);
+                (odds, shares - fee)
+            }
+        };
 
         Some(PotentialPayout {
             odds,
-            potential_payout: payout,
+            potential_payout: payout.into(),
             fee_rate,
         })
     }
 }
 
+impl QueryRoot {
+    /// Read a page of an account's ledger entries starting at `cursor`.
+    async fn ledger_page(
+        &self,
+        account: LedgerAccount,
+        cursor: Option<u64>,
+        limit: Option<u32>,
+    ) -> LedgerPage {
+        let count = self.state.get_ledger_count(&account).await;
+        let start = cursor.unwrap_or(0).min(count);
+        let limit = limit.unwrap_or(LEDGER_PAGE_SIZE).min(LEDGER_PAGE_SIZE) as u64;
+        let end = (start + limit).min(count);
+
+        let mut indices = Vec::new();
+        for index in start..end {
+            if let Ok(Some(position)) = self.state.account_ledger.get(&(account, index)).await {
+                indices.push(position as usize);
+            }
+        }
+        let entries = self
+            .state
+            .ledger
+            .multi_get(indices)
+            .await
+            .unwrap_or_default()
+            .into_iter()
+            .flatten()
+            .map(LedgerEntryObject::from)
+            .collect();
+
+        LedgerPage {
+            entries,
+            next_cursor: (end < count).then_some(end),
+        }
+    }
+
+    /// Look up the live status of a parlay's legs.
+    async fn parlay_status(&self, parlay: Parlay) -> ParlayStatus {
+        let mut legs = Vec::new();
+        for leg in &parlay.legs {
+            let status = match self.state.get_market(leg.market_id).await {
+                Some(market) => LivePredictState::leg_status(&market, leg.option_id),
+                None => LegStatus::Pending,
+            };
+            legs.push(status);
+        }
+
+        ParlayStatus {
+            id: parlay.id,
+            status: LivePredictState::parlay_status(&legs),
+            legs: parlay
+                .legs
+                .iter()
+                .zip(legs)
+                .map(|(leg, status)| ParlayLegStatus {
+                    market_id: leg.market_id,
+                    option_id: leg.option_id,
+                    odds: leg.odds,
+                    status,
+                })
+                .collect(),
+            amount: parlay.amount.into(),
+            odds: parlay.odds,
+            potential_payout: parlay.potential_payout.into(),
+            placed_at: parlay.placed_at,
+            settled: parlay.settled,
+            payout: parlay.payout.map(TokenAmount),
+        }
+    }
+}
+
+/// A betting market, with its amounts as token amounts.
+#[derive(async_graphql::SimpleObject)]
+#[graphql(name = "Market")]
+struct MarketObject {
+    /// Unique market identifier.
+    id: MarketId,
+    /// Match identifier this market belongs to.
+    match_id: String,
+    /// Type of market (e.g., "round_winner", "first_blood").
+    market_type: String,
+    /// Human-readable title.
+    title: String,
+    /// Shape of the options and how the market is resolved.
+    kind: MarketKind,
+    /// Total (over/under) or team A's spread (handicap) scaled by 1000, if any.
+    line: Option<i64>,
+    /// Team A and team B (handicap markets only).
+    teams: Vec<String>,
+    /// Bucket boundaries (range) or lower and upper bound (scalar) scaled by 1000.
+    bounds: Vec<i64>,
+    /// Owner who created the market.
+    creator: AccountOwner,
+    /// Currency stakes and payouts are denominated in.
+    currency: Currency,
+    /// Odds floor, cap and default (parimutuel and fixed-odds markets only).
+    odds_config: OddsConfig,
+    /// The market's own stake limits, unset limits falling back to the global ones.
+    limits: StakeLimitsObject,
+    /// Parent market outcome this market depends on, if any.
+    condition: Option<MarketCondition>,
+    /// How bets are priced and settled.
+    pricing: PricingMode,
+    /// Liquidity parameter `b` (LMSR markets only).
+    liquidity: TokenAmount,
+    /// Betting options.
+    options: Vec<MarketOptionObject>,
+    /// Current market status.
+    status: MarketStatus,
+    /// Timestamp when market was created.
+    created_at: Timestamp,
+    /// Timestamp when market locks (no more bets).
+    locks_at: Timestamp,
+    /// Winning options and the share of a full win each pays, empty until resolved.
+    winners: Vec<WinningOption>,
+    /// Numeric result scaled by 1000, if resolved by value.
+    result: Option<i64>,
+    /// Amount reserved for winning bets, set on resolution.
+    payout_pool: TokenAmount,
+    /// House liquidity reserved for the worst-case payout.
+    house_reserve: TokenAmount,
+    /// Stakes withheld by parimutuel cash-outs.
+    retained: TokenAmount,
+    /// Creator's seed on each option, empty if unseeded.
+    seed: Vec<TokenAmount>,
+    /// Amount returned to the creator for its seed, set on settlement.
+    seed_payout: Option<TokenAmount>,
+}
+
+impl From<Market> for MarketObject {
+    fn from(market: Market) -> Self {
+        MarketObject {
+            id: market.id,
+            match_id: market.match_id,
+            market_type: market.market_type,
+            title: market.title,
+            kind: market.kind,
+            line: market.line,
+            teams: market.teams,
+            bounds: market.bounds,
+            creator: market.creator,
+            currency: market.currency,
+            odds_config: market.odds_config,
+            limits: market.limits.into(),
+            condition: market.condition,
+            pricing: market.pricing,
+            liquidity: market.liquidity.into(),
+            options: market.options.into_iter().map(MarketOptionObject::from).collect(),
+            status: market.status,
+            created_at: market.created_at,
+            locks_at: market.locks_at,
+            winners: market.winners,
+            result: market.result,
+            payout_pool: market.payout_pool.into(),
+            house_reserve: market.house_reserve.into(),
+            retained: market.retained.into(),
+            seed: market.seed.into_iter().map(TokenAmount).collect(),
+            seed_payout: market.seed_payout.map(TokenAmount),
+        }
+    }
+}
+
+/// A betting option within a market.
+#[derive(async_graphql::SimpleObject)]
+#[graphql(name = "MarketOption")]
+struct MarketOptionObject {
+    /// Option identifier (0, 1, 2, etc.).
+    id: u8,
+    /// Human-readable label (e.g., "NAVI wins", "Over 6.5 kills").
+    label: String,
+    /// Total amount bet on this option.
+    pool: TokenAmount,
+    /// Odds offered (scaled by 1000, fixed-odds markets only).
+    odds: u32,
+    /// Gross payout owed if this option wins (fixed-odds and LMSR markets only).
+    liability: TokenAmount,
+    /// Whether the option was voided.
+    voided: bool,
+}
+
+impl From<MarketOption> for MarketOptionObject {
+    fn from(option: MarketOption) -> Self {
+        MarketOptionObject {
+            id: option.id,
+            label: option.label,
+            pool: option.pool.into(),
+            odds: option.odds,
+            liability: option.liability.into(),
+            voided: option.voided,
+        }
+    }
+}
+
+/// A user's bet on a market option.
+#[derive(async_graphql::SimpleObject)]
+#[graphql(name = "Bet")]
+struct BetObject {
+    /// Unique bet identifier.
+    id: BetId,
+    /// Account owner who placed the bet.
+    owner: AccountOwner,
+    /// Market this bet is for.
+    market_id: MarketId,
+    /// Selected option ID.
+    option_id: u8,
+    /// Amount wagered.
+    amount: TokenAmount,
+    /// Odds quoted when the bet was placed (scaled by 1000).
+    odds: u32,
+    /// Shares held (fixed-odds and LMSR markets only).
+    shares: TokenAmount,
+    /// Timestamp when bet was placed.
+    placed_at: Timestamp,
+    /// Whether bet has been settled.
+    settled: bool,
+    /// Whether bet was cashed out before the market locked.
+    cashed_out: bool,
+    /// Bet this record was split from by a partial cash-out.
+    split_from: Option<BetId>,
+    /// Amount credited on settlement, if settled.
+    payout: Option<TokenAmount>,
+}
+
+impl From<Bet> for BetObject {
+    fn from(bet: Bet) -> Self {
+        BetObject {
+            id: bet.id,
+            owner: bet.owner,
+            market_id: bet.market_id,
+            option_id: bet.option_id,
+            amount: bet.amount.into(),
+            odds: bet.odds,
+            shares: bet.shares.into(),
+            placed_at: bet.placed_at,
+            settled: bet.settled,
+            cashed_out: bet.cashed_out,
+            split_from: bet.split_from,
+            payout: bet.payout.map(TokenAmount),
+        }
+    }
+}
+
+/// A parlay with the live status of its legs.
+#[derive(async_graphql::SimpleObject)]
+struct ParlayStatus {
+    /// Parlay identifier.
+    id: ParlayId,
+    /// Overall outcome so far.
+    status: LegStatus,
+    /// Selections and their outcomes.
+    legs: Vec<ParlayLegStatus>,
+    /// Amount wagered.
+    amount: TokenAmount,
+    /// Combined odds when the parlay was placed (scaled by 1000).
+    odds: u32,
+    /// Gross payout if every leg wins.
+    potential_payout: TokenAmount,
+    /// Timestamp when parlay was placed.
+    placed_at: Timestamp,
+    /// Whether parlay has been settled.
+    settled: bool,
+    /// Payout amount (if settled).
+    payout: Option<TokenAmount>,
+}
+
+/// A parlay leg and its outcome so far.
+#[derive(async_graphql::SimpleObject)]
+struct ParlayLegStatus {
+    /// Market of the leg.
+    market_id: MarketId,
+    /// Selected option.
+    option_id: u8,
+    /// Odds when the parlay was placed (scaled by 1000).
+    odds: u32,
+    /// Outcome so far.
+    status: LegStatus,
+}
+
+/// A market option and the line it is bet against.
+#[derive(async_graphql::SimpleObject)]
+struct OptionLine {
+    /// Option identifier.
+    option_id: u8,
+    /// Human-readable label.
+    label: String,
+    /// Total (over/under) or spread (handicap) scaled by 1000, if any.
+    line: Option<i64>,
+}
+
+/// The creator's seed on a market option.
+#[derive(async_graphql::SimpleObject)]
+struct SeedLiquidity {
+    /// Option identifier.
+    option_id: u8,
+    /// Seed on the option.
+    amount: TokenAmount,
+    /// Total pool of the option, seed included.
+    pool: TokenAmount,
+}
+
 /// Potential payout calculation result.
 #[derive(async_graphql::SimpleObject)]
 struct PotentialPayout {
     /// Odds (scaled by 1000).
     odds: u32,
-    /// Potential payout after fees.
-    potential_payout: Amount,
+    /// Estimated payout after fees if the pools don't change.
+    potential_payout: TokenAmount,
     /// Fee rate in basis points.
     fee_rate: u32,
 }
 
+/// Cash-out quote for a bet.
+#[derive(async_graphql::SimpleObject)]
+struct CashOutQuote {
+    /// Current value of the bet before fees.
+    value: TokenAmount,
+    /// Protocol fee on the value.
+    fee: TokenAmount,
+    /// Amount credited on cash-out.
+    payout: TokenAmount,
+}
+
+/// Convert a price (scaled by `lmsr::PRICE_SCALE`) to odds (scaled by 1000), or back.
+fn invert_price(value: u32) -> u32 {
+    (1000 * lmsr::PRICE_SCALE as u64)
+        .checked_div(value as u64)
+        .map_or(0, |inverse| inverse.min(u32::MAX as u64) as u32)
+}
+
+/// Current price of a market option.
+#[derive(async_graphql::SimpleObject)]
+struct OptionPrice {
+    /// Option identifier.
+    option_id: u8,
+    /// Price per share as a probability (scaled by 1_000_000).
+    price: u32,
+    /// Odds matching the price (scaled by 1000).
+    odds: u32,
+}
+
+/// A page of a ledger account's entries.
+#[derive(async_graphql::SimpleObject)]
+struct LedgerPage {
+    /// Entries on this page, oldest first.
+    entries: Vec<LedgerEntryObject>,
+    /// Cursor of the next page, if there are more entries.
+    next_cursor: Option<u64>,
+}
+
+/// A movement of funds from one ledger account to another.
+#[derive(async_graphql::SimpleObject)]
+#[graphql(name = "LedgerEntry")]
+struct LedgerEntryObject {
+    /// Account the funds are taken from.
+    debit: LedgerAccountObject,
+    /// Account the funds are added to.
+    credit: LedgerAccountObject,
+    /// Currency of the funds.
+    currency: Currency,
+    /// Amount moved.
+    amount: TokenAmount,
+    /// Why the funds moved.
+    reason: LedgerReason,
+    /// Related market, none for parlays and owner, house or protocol transfers.
+    market_id: Option<MarketId>,
+    /// Related bet, or parlay for parlay stakes and payouts.
+    bet_id: Option<BetId>,
+    /// Timestamp of the movement.
+    timestamp: Timestamp,
+}
+
+impl From<LedgerEntry> for LedgerEntryObject {
+    fn from(entry: LedgerEntry) -> Self {
+        LedgerEntryObject {
+            debit: entry.debit.into(),
+            credit: entry.credit.into(),
+            currency: entry.currency,
+            amount: entry.amount.into(),
+            reason: entry.reason,
+            market_id: entry.market_id,
+            bet_id: entry.bet_id,
+            timestamp: entry.timestamp,
+        }
+    }
+}
+
+/// Kind of a ledger account.
+#[derive(Clone, Copy, PartialEq, Eq, async_graphql::Enum)]
+enum LedgerAccountKind {
+    /// An owner's available balance.
+    Owner,
+    /// An owner's token account outside the application.
+    External,
+    /// Accumulated protocol fees.
+    Protocol,
+    /// The house liquidity pool.
+    House,
+    /// Stakes, seeds and house reserve held by a market.
+    Market,
+    /// Stake and house reserve held by a parlay.
+    Parlay,
+}
+
+/// A ledger account, flattened for GraphQL.
+#[derive(async_graphql::SimpleObject, async_graphql::InputObject)]
+#[graphql(name = "LedgerAccount", input_name = "LedgerAccountInput")]
+struct LedgerAccountObject {
+    /// Kind of account.
+    kind: LedgerAccountKind,
+    /// Owner of owner and external accounts.
+    owner: Option<AccountOwner>,
+    /// Market or parlay of market and parlay accounts.
+    id: Option<u64>,
+}
+
+impl LedgerAccountObject {
+    /// The account described, if its owner or ID is given where its kind needs one.
+    fn account(&self) -> Option<LedgerAccount> {
+        Some(match self.kind {
+            LedgerAccountKind::Owner => LedgerAccount::Owner(self.owner?),
+            LedgerAccountKind::External => LedgerAccount::External(self.owner?),
+            LedgerAccountKind::Protocol => LedgerAccount::Protocol,
+            LedgerAccountKind::House => LedgerAccount::House,
+            LedgerAccountKind::Market => LedgerAccount::Market(self.id?),
+            LedgerAccountKind::Parlay => LedgerAccount::Parlay(self.id?),
+        })
+    }
+}
+
+impl From<LedgerAccount> for LedgerAccountObject {
+    fn from(account: LedgerAccount) -> Self {
+        let (kind, owner, id) = match account {
+            LedgerAccount::Owner(owner) => (LedgerAccountKind::Owner, Some(owner), None),
+            LedgerAccount::External(owner) => (LedgerAccountKind::External, Some(owner), None),
+            LedgerAccount::Protocol => (LedgerAccountKind::Protocol, None, None),
+            LedgerAccount::House => (LedgerAccountKind::House, None, None),
+            LedgerAccount::Market(id) => (LedgerAccountKind::Market, None, Some(id)),
+            LedgerAccount::Parlay(id) => (LedgerAccountKind::Parlay, None, Some(id)),
+        };
+        LedgerAccountObject { kind, owner, id }
+    }
+}
+
+/// An owner's balance split into available funds and locked stakes.
+#[derive(async_graphql::SimpleObject)]
+struct BalanceBreakdown {
+    /// Balance available to bet or withdraw.
+    available: TokenAmount,
+    /// Stake locked in unsettled bets, parlays and market seeds.
+    locked: TokenAmount,
+    /// Available plus locked.
+    total: TokenAmount,
+}
+
+/// House liquidity and lifetime profit and loss.
+#[derive(async_graphql::SimpleObject)]
+struct HouseSummary {
+    /// Unreserved liquidity available to back house-backed markets.
+    liquidity: TokenAmount,
+    /// Total profit from settled fixed-odds and LMSR markets.
+    profit: TokenAmount,
+    /// Total loss from settled fixed-odds and LMSR markets.
+    loss: TokenAmount,
+}
+
+/// A token amount, exchanged over GraphQL as a decimal string since `u128` has no
+/// GraphQL scalar. Inputs may also be given as integers.
+#[derive(Clone, Copy)]
+struct TokenAmount(Amount);
+
+impl From<Amount> for TokenAmount {
+    fn from(amount: Amount) -> Self {
+        TokenAmount(amount)
+    }
+}
+
+#[async_graphql::Scalar]
+impl async_graphql::ScalarType for TokenAmount {
+    fn parse(value: async_graphql::Value) -> async_graphql::InputValueResult<Self> {
+        match &value {
+            async_graphql::Value::String(amount) => Ok(TokenAmount(amount.parse()?)),
+            async_graphql::Value::Number(amount) => match amount.as_u64() {
+                Some(amount) => Ok(TokenAmount(amount.into())),
+                None => Err(async_graphql::InputValueError::expected_type(value)),
+            },
+            _ => Err(async_graphql::InputValueError::expected_type(value)),
+        }
+    }
+
+    fn to_value(&self) -> async_graphql::Value {
+        async_graphql::Value::String(self.0.to_string())
+    }
+}
+
+/// Stake limits of a market or global stake limits, in token amounts.
+#[derive(async_graphql::SimpleObject, async_graphql::InputObject)]
+#[graphql(name = "StakeLimits", input_name = "StakeLimitsInput")]
+struct StakeLimitsObject {
+    /// Largest single stake.
+    max_stake: Option<TokenAmount>,
+    /// Largest total an owner can stake on a market.
+    max_owner_stake: Option<TokenAmount>,
+    /// Largest total pool of a market.
+    max_pool: Option<TokenAmount>,
+}
+
+impl From<StakeLimits> for StakeLimitsObject {
+    fn from(limits: StakeLimits) -> Self {
+        StakeLimitsObject {
+            max_stake: limits.max_stake.map(TokenAmount),
+            max_owner_stake: limits.max_owner_stake.map(TokenAmount),
+            max_pool: limits.max_pool.map(TokenAmount),
+        }
+    }
+}
+
+impl From<StakeLimitsObject> for StakeLimits {
+    fn from(limits: StakeLimitsObject) -> Self {
+        StakeLimits {
+            max_stake: limits.max_stake.map(|amount| amount.0),
+            max_owner_stake: limits.max_owner_stake.map(|amount| amount.0),
+            max_pool: limits.max_pool.map(|amount| amount.0),
+        }
+    }
+}
+
 /// GraphQL mutation root.
 struct MutationRoot {
     runtime: Arc<ServiceRuntime<LivePredictService>>,
@@ -197,13 +1015,24 @@ struct MutationRoot {
 #[Object]
 impl MutationRoot {
     /// Create a new market.
+    #[allow(clippy::too_many_arguments)]
     async fn create_market(
         &self,
         match_id: String,
         market_type: String,
         title: String,
         options: Vec<String>,
-        locks_at: u64,
+        locks_at: Timestamp,
+        kind: MarketKind,
+        line: Option<i64>,
+        bounds: Vec<i64>,
+        pricing: PricingMode,
+        liquidity: TokenAmount,
+        condition: Option<ConditionInput>,
+        limits: StakeLimitsObject,
+        odds_config: Option<OddsConfig>,
+        seed: Vec<TokenAmount>,
+        currency: Currency,
     ) -> [u8; 0] {
         let operation = Operation::CreateMarket {
             match_id,
@@ -211,6 +1040,16 @@ impl MutationRoot {
             title,
             options,
             locks_at,
+            kind,
+            line,
+            bounds,
+            pricing,
+            liquidity: liquidity.0,
+            condition: condition.map(|c| (c.market_id, c.option_id)),
+            limits: limits.into(),
+            odds_config,
+            seed: seed.into_iter().map(|amount| amount.0).collect(),
+            currency,
         };
         self.runtime.schedule_operation(&operation);
         []
@@ -221,12 +1060,14 @@ impl MutationRoot {
         &self,
         market_id: MarketId,
         option_id: u8,
-        amount: Amount,
+        amount: TokenAmount,
+        min_odds: Option<u32>,
     ) -> [u8; 0] {
         let operation = Operation::PlaceBet {
             market_id,
             option_id,
-            amount,
+            amount: amount.0,
+            min_odds,
         };
         self.runtime.schedule_operation(&operation);
         []
@@ -249,6 +1090,44 @@ impl MutationRoot {
         []
     }
 
+    /// Resolve a market with several winning options.
+    async fn resolve_winners(&self, market_id: MarketId, winners: Vec<WinnerInput>) -> [u8; 0] {
+        let operation = Operation::ResolveWinners {
+            market_id,
+            winners: winners.into_iter().map(|w| (w.option_id, w.weight)).collect(),
+        };
+        self.runtime.schedule_operation(&operation);
+        []
+    }
+
+    /// Resolve an over/under, range or scalar market from its numeric result.
+    async fn resolve_scalar(&self, market_id: MarketId, value: i64) -> [u8; 0] {
+        let operation = Operation::ResolveScalar { market_id, value };
+        self.runtime.schedule_operation(&operation);
+        []
+    }
+
+    /// Void a single option of a market.
+    async fn void_option(&self, market_id: MarketId, option_id: u8) -> [u8; 0] {
+        let operation = Operation::VoidOption {
+            market_id,
+            option_id,
+        };
+        self.runtime.schedule_operation(&operation);
+        []
+    }
+
+    /// Resolve a handicap market from the final score.
+    async fn resolve_score(&self, market_id: MarketId, score_a: u32, score_b: u32) -> [u8; 0] {
+        let operation = Operation::ResolveScore {
+            market_id,
+            score_a,
+            score_b,
+        };
+        self.runtime.schedule_operation(&operation);
+        []
+    }
+
     /// Cancel a market.
     async fn cancel_market(&self, market_id: MarketId) -> [u8; 0] {
         let operation = Operation::CancelMarket { market_id };
@@ -256,6 +1135,78 @@ impl MutationRoot {
         []
     }
 
+    /// Suspend a market.
+    async fn suspend_market(&self, market_id: MarketId) -> [u8; 0] {
+        let operation = Operation::SuspendMarket { market_id };
+        self.runtime.schedule_operation(&operation);
+        []
+    }
+
+    /// Resume a suspended market.
+    async fn resume_market(&self, market_id: MarketId) -> [u8; 0] {
+        let operation = Operation::ResumeMarket { market_id };
+        self.runtime.schedule_operation(&operation);
+        []
+    }
+
+    /// Set fixed odds for each option of a market.
+    async fn set_odds(&self, market_id: MarketId, odds: Vec<u32>) -> [u8; 0] {
+        let operation = Operation::SetOdds { market_id, odds };
+        self.runtime.schedule_operation(&operation);
+        []
+    }
+
+    /// Fund the house liquidity pool.
+    async fn fund_house(&self, currency: Currency, amount: TokenAmount) -> [u8; 0] {
+        let operation = Operation::FundHouse {
+            currency,
+            amount: amount.0,
+        };
+        self.runtime.schedule_operation(&operation);
+        []
+    }
+
+    /// Withdraw unreserved house liquidity.
+    async fn withdraw_house(&self, currency: Currency, amount: TokenAmount) -> [u8; 0] {
+        let operation = Operation::WithdrawHouse {
+            currency,
+            amount: amount.0,
+        };
+        self.runtime.schedule_operation(&operation);
+        []
+    }
+
+    /// Set the global stake limits.
+    async fn set_stake_limits(&self, limits: StakeLimitsObject) -> [u8; 0] {
+        let operation = Operation::SetStakeLimits { limits: limits.into() };
+        self.runtime.schedule_operation(&operation);
+        []
+    }
+
+    /// Grant a role to an owner.
+    async fn grant_role(&self, owner: AccountOwner, role: Role) -> [u8; 0] {
+        let operation = Operation::GrantRole { owner, role };
+        self.runtime.schedule_operation(&operation);
+        []
+    }
+
+    /// Revoke a role from an owner.
+    async fn revoke_role(&self, owner: AccountOwner, role: Role) -> [u8; 0] {
+        let operation = Operation::RevokeRole { owner, role };
+        self.runtime.schedule_operation(&operation);
+        []
+    }
+
+    /// Withdraw protocol fees to the caller's balance.
+    async fn withdraw_fees(&self, currency: Currency, amount: TokenAmount) -> [u8; 0] {
+        let operation = Operation::WithdrawFees {
+            currency,
+            amount: amount.0,
+        };
+        self.runtime.schedule_operation(&operation);
+        []
+    }
+
     /// Claim winnings.
     async fn claim_winnings(&self, bet_id: BetId) -> [u8; 0] {
         let operation = Operation::ClaimWinnings { bet_id };
@@ -263,17 +1214,199 @@ impl MutationRoot {
         []
     }
 
+    /// Cash out a bet.
+    async fn cash_out(&self, bet_id: BetId, min_value: TokenAmount) -> [u8; 0] {
+        let operation = Operation::CashOut {
+            bet_id,
+            min_value: min_value.0,
+        };
+        self.runtime.schedule_operation(&operation);
+        []
+    }
+
+    /// Cash out part of a bet.
+    async fn partial_cash_out(
+        &self,
+        bet_id: BetId,
+        fraction_bps: u32,
+        min_value: TokenAmount,
+    ) -> [u8; 0] {
+        let operation = Operation::PartialCashOut {
+            bet_id,
+            fraction_bps,
+            min_value: min_value.0,
+        };
+        self.runtime.schedule_operation(&operation);
+        []
+    }
+
+    /// Place a parlay across several markets.
+    async fn place_parlay(&self, legs: Vec<ParlayLegInput>, amount: TokenAmount) -> [u8; 0] {
+        let operation = Operation::PlaceParlay {
+            legs: legs.into_iter().map(|leg| (leg.market_id, leg.option_id)).collect(),
+            amount: amount.0,
+        };
+        self.runtime.schedule_operation(&operation);
+        []
+    }
+
+    /// Settle a parlay.
+    async fn settle_parlay(&self, parlay_id: ParlayId) -> [u8; 0] {
+        let operation = Operation::SettleParlay { parlay_id };
+        self.runtime.schedule_operation(&operation);
+        []
+    }
+
     /// Deposit tokens.
-    async fn deposit(&self, amount: Amount) -> [u8; 0] {
-        let operation = Operation::Deposit { amount };
+    async fn deposit(&self, currency: Currency, amount: TokenAmount) -> [u8; 0] {
+        let operation = Operation::Deposit {
+            currency,
+            amount: amount.0,
+        };
         self.runtime.schedule_operation(&operation);
         []
     }
 
     /// Withdraw tokens.
-    async fn withdraw(&self, amount: Amount) -> [u8; 0] {
-        let operation = Operation::Withdraw { amount };
+    async fn withdraw(&self, currency: Currency, amount: TokenAmount) -> [u8; 0] {
+        let operation = Operation::Withdraw {
+            currency,
+            amount: amount.0,
+        };
         self.runtime.schedule_operation(&operation);
         []
     }
 }
+
+/// Parent market outcome a new market is conditional on.
+#[derive(async_graphql::InputObject)]
+struct ConditionInput {
+    /// Parent market.
+    market_id: MarketId,
+    /// Option the parent market must resolve to.
+    option_id: u8,
+}
+
+/// A parlay selection.
+#[derive(async_graphql::InputObject)]
+struct ParlayLegInput {
+    /// Market of the leg.
+    market_id: MarketId,
+    /// Selected option.
+    option_id: u8,
+}
+
+/// A winning option when resolving a market.
+#[derive(async_graphql::InputObject)]
+struct WinnerInput {
+    /// Winning option.
+    option_id: u8,
+    /// Share of a full win in basis points, full win if omitted.
+    weight: Option<u32>,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use async_graphql::{value, Request};
+    use linera_sdk::{linera_base_types::AccountOwner, views::View, Service, ServiceRuntime};
+    use live_predict::{
+        Bet, Market, MarketKind, MarketStatus, PricingMode, Timestamp, WinningOption,
+    };
+
+    use super::{test_support::test_market, LivePredictService, LivePredictState};
+
+    /// A resolved two-option market where both options dead-heated.
+    fn dead_heat_market() -> Market {
+        let mut market = test_market(
+            MarketKind::Categorical,
+            PricingMode::Parimutuel,
+            &[500_000_000, 500_000_000],
+        );
+        market.status = MarketStatus::Resolved;
+        market.winners = vec![
+            WinningOption {
+                option_id: 0,
+                weight: 5000,
+            },
+            WinningOption {
+                option_id: 1,
+                weight: 5000,
+            },
+        ];
+        market.payout_pool = 990_000_000;
+        market
+    }
+
+    #[tokio::test]
+    async fn market_query_exposes_winners_and_amounts() {
+        let runtime = ServiceRuntime::<LivePredictService>::new();
+        let mut state = LivePredictState::load(runtime.root_view_storage_context())
+            .await
+            .expect("Failed to load state");
+        state.markets.insert(&1, dead_heat_market()).expect("Failed to insert market");
+        let service = LivePredictService {
+            state: Arc::new(state),
+            runtime: Arc::new(runtime),
+        };
+
+        let query = "{ market(id: 1) { status payoutPool oddsConfig { cap } options { pool } \
+                     winners { optionId weight } } }";
+        let response = service.handle_query(Request::new(query)).await;
+        assert_eq!(
+            response.data,
+            value!({
+                "market": {
+                    "status": "RESOLVED",
+                    "payoutPool": "990000000",
+                    "oddsConfig": {"cap": 10000},
+                    "options": [{"pool": "500000000"}, {"pool": "500000000"}],
+                    "winners": [
+                        {"optionId": 0, "weight": 5000},
+                        {"optionId": 1, "weight": 5000},
+                    ],
+                }
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn cash_out_quotes_stop_at_lock_time() {
+        let runtime = ServiceRuntime::<LivePredictService>::new();
+        let mut state = LivePredictState::load(runtime.root_view_storage_context())
+            .await
+            .expect("Failed to load state");
+        let market = test_market(MarketKind::Categorical, PricingMode::Parimutuel, &[100, 100]);
+        let locks_at = market.locks_at;
+        state.markets.insert(&1, market).expect("Failed to insert market");
+        let bet = Bet {
+            id: 1,
+            owner: AccountOwner::Address20([2; 20]),
+            market_id: 1,
+            option_id: 0,
+            amount: 100,
+            odds: 2000,
+            shares: 0,
+            placed_at: Timestamp::from(0),
+            settled: false,
+            cashed_out: false,
+            split_from: None,
+            payout: None,
+        };
+        state.bets.insert(&1, bet).expect("Failed to insert bet");
+        let runtime = Arc::new(runtime.with_system_time(Timestamp::from(0)));
+        let service = LivePredictService {
+            state: Arc::new(state),
+            runtime: runtime.clone(),
+        };
+
+        let query = "{ cashOutQuote(betId: 1) { payout } }";
+        let response = service.handle_query(Request::new(query)).await;
+        assert_eq!(response.data, value!({"cashOutQuote": {"payout": "100"}}));
+
+        runtime.set_system_time(locks_at);
+        let response = service.handle_query(Request::new(query)).await;
+        assert_eq!(response.data, value!({"cashOutQuote": null}));
+    }
+}
@@ -7,13 +7,19 @@
 //! efficient queries and updates.
 
 use linera_sdk::views::{
-    linera_views, MapView, RegisterView, RootView, ViewStorageContext,
+    linera_views, LogView, MapView, RegisterView, RootView, ViewStorageContext,
 };
-use live_predict::{Amount, Bet, BetId, Market, MarketId};
+use linera_sdk::linera_base_types::AccountOwner;
+use live_predict::{
+    Amount, Backing, Bet, BetId, Currency, LedgerAccount, LedgerEntry, LegStatus, Market, MarketId,
+    MarketKind, MarketStatus, Parlay, OddsConfig, ParlayId, PricingMode, Role, StakeLimits,
+};
+
+use crate::lmsr;
 
 /// The main application state stored on-chain.
 #[derive(RootView, async_graphql::SimpleObject)]
-#[view(context = ViewStorageContext)]
+#[view(context = "ViewStorageContext")]
 pub struct LivePredictState {
     /// Counter for generating unique market IDs.
     pub next_market_id: RegisterView<MarketId>,
@@ -21,6 +27,9 @@ pub struct LivePredictState {
     /// Counter for generating unique bet IDs.
     pub next_bet_id: RegisterView<BetId>,
     
+    /// Counter for generating unique parlay IDs.
+    pub next_parlay_id: RegisterView<ParlayId>,
+    
     /// All betting markets indexed by ID.
     #[graphql(skip)]
     pub markets: MapView<MarketId, Market>,
@@ -29,46 +38,119 @@ pub struct LivePredictState {
     #[graphql(skip)]
     pub bets: MapView<BetId, Bet>,
     
-    /// User balances indexed by chain ID (owner address).
+    /// All parlays indexed by ID.
+    #[graphql(skip)]
+    pub parlays: MapView<ParlayId, Parlay>,
+    
+    /// User balances indexed by account owner and currency.
+    #[graphql(skip)]
+    pub balances: MapView<(AccountOwner, Currency), Amount>,
+    
+    /// Stakes locked in unsettled bets and parlays, and seeds of unsettled markets,
+    /// indexed by account owner and currency. Kept apart from `balances`, which only
+    /// hold available funds.
     #[graphql(skip)]
-    pub balances: MapView<String, Amount>,
+    pub locked_balances: MapView<(AccountOwner, Currency), Amount>,
     
-    /// Bets by user (chain ID -> list of bet IDs).
+    /// Append-only double-entry ledger of every movement of funds.
     #[graphql(skip)]
-    pub user_bets: MapView<String, Vec<BetId>>,
+    pub ledger: LogView<LedgerEntry>,
+    
+    /// Ledger entries by account ((account, index among the account's entries) ->
+    /// position in the ledger).
+    #[graphql(skip)]
+    pub account_ledger: MapView<(LedgerAccount, u64), u64>,
+    
+    /// Number of ledger entries of each account.
+    #[graphql(skip)]
+    pub account_ledger_counts: MapView<LedgerAccount, u64>,
+    
+    /// Bets by user (account owner -> list of bet IDs).
+    #[graphql(skip)]
+    pub user_bets: MapView<AccountOwner, Vec<BetId>>,
+    
+    /// Parlays by user (account owner -> list of parlay IDs).
+    #[graphql(skip)]
+    pub user_parlays: MapView<AccountOwner, Vec<ParlayId>>,
     
     /// Bets by market (market ID -> list of bet IDs).
     #[graphql(skip)]
     pub market_bets: MapView<MarketId, Vec<BetId>>,
     
+    /// Partial cash-outs by bet (bet ID -> settled bet IDs split from it).
+    #[graphql(skip)]
+    pub bet_splits: MapView<BetId, Vec<BetId>>,
+    
+    /// Total staked by each owner on each market.
+    #[graphql(skip)]
+    pub owner_stakes: MapView<(MarketId, AccountOwner), Amount>,
+    
+    /// Conditional markets by parent (market ID -> dependent market IDs).
+    #[graphql(skip)]
+    pub market_dependents: MapView<MarketId, Vec<MarketId>>,
+    
+    /// Roles granted to each owner.
+    #[graphql(skip)]
+    pub roles: MapView<AccountOwner, Vec<Role>>,
+    
     /// Active (open) market IDs for quick lookup.
     pub active_markets: RegisterView<Vec<MarketId>>,
     
-    /// Total volume of all bets (lifetime).
-    pub total_volume: RegisterView<Amount>,
+    /// Total volume of all bets by currency (lifetime).
+    #[graphql(skip)]
+    pub total_volume: MapView<Currency, Amount>,
+    
+    /// Global stake limits, applying to markets without their own.
+    #[graphql(skip)]
+    pub stake_limits: RegisterView<StakeLimits>,
     
     /// Protocol fee rate (basis points, e.g., 100 = 1%).
     pub fee_rate_bps: RegisterView<u32>,
     
-    /// Accumulated protocol fees.
-    pub protocol_fees: RegisterView<Amount>,
+    /// Tokens backing user balances.
+    pub backing: RegisterView<Backing>,
+    
+    /// Accumulated protocol fees by currency.
+    #[graphql(skip)]
+    pub protocol_fees: MapView<Currency, Amount>,
+    
+    /// House liquidity available to back fixed-odds and LMSR markets, by currency.
+    #[graphql(skip)]
+    pub house_liquidity: MapView<Currency, Amount>,
+    
+    /// Total house profit from settled fixed-odds and LMSR markets by currency
+    /// (lifetime).
+    #[graphql(skip)]
+    pub house_profit: MapView<Currency, Amount>,
+    
+    /// Total house loss from settled fixed-odds and LMSR markets by currency
+    /// (lifetime).
+    #[graphql(skip)]
+    pub house_loss: MapView<Currency, Amount>,
 }
 
 impl LivePredictState {
     /// Get the next market ID and increment counter.
     pub async fn allocate_market_id(&mut self) -> MarketId {
-        let id = self.next_market_id.get();
+        let id = *self.next_market_id.get();
         self.next_market_id.set(id + 1);
         id
     }
     
     /// Get the next bet ID and increment counter.
     pub async fn allocate_bet_id(&mut self) -> BetId {
-        let id = self.next_bet_id.get();
+        let id = *self.next_bet_id.get();
         self.next_bet_id.set(id + 1);
         id
     }
     
+    /// Get the next parlay ID and increment counter.
+    pub async fn allocate_parlay_id(&mut self) -> ParlayId {
+        let id = *self.next_parlay_id.get();
+        self.next_parlay_id.set(id + 1);
+        id
+    }
+    
     /// Get a market by ID.
     pub async fn get_market(&self, market_id: MarketId) -> Option<Market> {
         self.markets.get(&market_id).await.ok().flatten()
@@ -79,21 +161,150 @@ impl LivePredictState {
         self.bets.get(&bet_id).await.ok().flatten()
     }
     
-    /// Get user balance.
-    pub async fn get_balance(&self, owner: &str) -> Amount {
-        self.balances.get(&owner.to_string()).await.ok().flatten().unwrap_or(0)
+    /// Get a parlay by ID.
+    pub async fn get_parlay(&self, parlay_id: ParlayId) -> Option<Parlay> {
+        self.parlays.get(&parlay_id).await.ok().flatten()
+    }
+    
+    /// Get user balance in a currency.
+    pub async fn get_balance(&self, owner: &AccountOwner, currency: &Currency) -> Amount {
+        let key = (*owner, currency.clone());
+        self.balances.get(&key).await.ok().flatten().unwrap_or(0)
+    }
+    
+    /// Update user balance in a currency. Balances only change through `post_entry`, so
+    /// every movement is recorded in the ledger.
+    async fn set_balance(&mut self, owner: &AccountOwner, currency: &Currency, amount: Amount) {
+        self.balances
+            .insert(&(*owner, currency.clone()), amount)
+            .expect("Failed to update balance");
+    }
+    
+    /// Apply a ledger entry to the owner balances on either side and append it to the
+    /// ledger under both accounts, skipping entries that move nothing. The balances of
+    /// the other accounts are kept by the protocol fee, house and market bookkeeping.
+    /// Debits expect the balance to have been checked.
+    pub async fn post_entry(&mut self, entry: LedgerEntry) {
+        if entry.amount == 0 {
+            return;
+        }
+        if let LedgerAccount::Owner(owner) = entry.debit {
+            let balance = self.get_balance(&owner, &entry.currency).await;
+            self.set_balance(&owner, &entry.currency, balance - entry.amount).await;
+        }
+        if let LedgerAccount::Owner(owner) = entry.credit {
+            let balance = self.get_balance(&owner, &entry.currency).await;
+            self.set_balance(&owner, &entry.currency, balance + entry.amount).await;
+        }
+        
+        let position = self.ledger.count() as u64;
+        self.index_entry(entry.debit, position).await;
+        if entry.credit != entry.debit {
+            self.index_entry(entry.credit, position).await;
+        }
+        self.ledger.push(entry);
+    }
+    
+    /// Add a ledger position to an account's entries.
+    async fn index_entry(&mut self, account: LedgerAccount, position: u64) {
+        let index = self.get_ledger_count(&account).await;
+        self.account_ledger
+            .insert(&(account, index), position)
+            .expect("Failed to index ledger entry");
+        self.account_ledger_counts
+            .insert(&account, index + 1)
+            .expect("Failed to count ledger entry");
+    }
+    
+    /// Get the number of ledger entries of an account.
+    pub async fn get_ledger_count(&self, account: &LedgerAccount) -> u64 {
+        self.account_ledger_counts.get(account).await.ok().flatten().unwrap_or(0)
+    }
+    
+    /// Get the stake an owner has locked in unsettled bets, parlays and seeds in a
+    /// currency.
+    pub async fn get_locked(&self, owner: &AccountOwner, currency: &Currency) -> Amount {
+        let key = (*owner, currency.clone());
+        self.locked_balances.get(&key).await.ok().flatten().unwrap_or(0)
     }
     
-    /// Update user balance.
-    pub async fn set_balance(&mut self, owner: &str, amount: Amount) {
-        self.balances.insert(&owner.to_string(), amount).expect("Failed to update balance");
+    /// Lock a stake or seed placed by an owner until its bet, parlay or market is
+    /// settled.
+    pub async fn lock_stake(&mut self, owner: &AccountOwner, currency: &Currency, amount: Amount) {
+        let locked = self.get_locked(owner, currency).await + amount;
+        self.locked_balances
+            .insert(&(*owner, currency.clone()), locked)
+            .expect("Failed to lock stake");
+    }
+    
+    /// Unlock the stake of a settled bet or parlay, or a returned seed.
+    pub async fn unlock_stake(
+        &mut self,
+        owner: &AccountOwner,
+        currency: &Currency,
+        amount: Amount,
+    ) {
+        let locked = self.get_locked(owner, currency).await.saturating_sub(amount);
+        self.locked_balances
+            .insert(&(*owner, currency.clone()), locked)
+            .expect("Failed to unlock stake");
+    }
+    
+    /// Get a per-currency total, zero if unset.
+    async fn get_total(totals: &MapView<Currency, Amount>, currency: &Currency) -> Amount {
+        totals.get(currency).await.ok().flatten().unwrap_or(0)
+    }
+    
+    /// Get the total volume of bets in a currency.
+    pub async fn get_total_volume(&self, currency: &Currency) -> Amount {
+        Self::get_total(&self.total_volume, currency).await
+    }
+    
+    /// Add to the total volume of bets in a currency.
+    pub async fn add_total_volume(&mut self, currency: &Currency, amount: Amount) {
+        let total = self.get_total_volume(currency).await + amount;
+        self.total_volume.insert(currency, total).expect("Failed to update volume");
+    }
+    
+    /// Get the protocol fees accumulated in a currency.
+    pub async fn get_protocol_fees(&self, currency: &Currency) -> Amount {
+        Self::get_total(&self.protocol_fees, currency).await
+    }
+    
+    /// Update the protocol fees accumulated in a currency.
+    pub fn set_protocol_fees(&mut self, currency: &Currency, amount: Amount) {
+        self.protocol_fees.insert(currency, amount).expect("Failed to update protocol fees");
+    }
+    
+    /// Add to the protocol fees accumulated in a currency.
+    pub async fn add_protocol_fees(&mut self, currency: &Currency, amount: Amount) {
+        let total = self.get_protocol_fees(currency).await + amount;
+        self.set_protocol_fees(currency, total);
+    }
+    
+    /// Get the house liquidity available in a currency.
+    pub async fn get_house_liquidity(&self, currency: &Currency) -> Amount {
+        Self::get_total(&self.house_liquidity, currency).await
+    }
+    
+    /// Update the house liquidity available in a currency.
+    pub fn set_house_liquidity(&mut self, currency: &Currency, amount: Amount) {
+        self.house_liquidity.insert(currency, amount).expect("Failed to update house liquidity");
+    }
+    
+    /// Get the house's lifetime profit and loss in a currency.
+    pub async fn get_house_result(&self, currency: &Currency) -> (Amount, Amount) {
+        (
+            Self::get_total(&self.house_profit, currency).await,
+            Self::get_total(&self.house_loss, currency).await,
+        )
     }
     
     /// Add a bet to user's bet list.
-    pub async fn add_user_bet(&mut self, owner: &str, bet_id: BetId) {
-        let mut bets = self.user_bets.get(&owner.to_string()).await.ok().flatten().unwrap_or_default();
+    pub async fn add_user_bet(&mut self, owner: &AccountOwner, bet_id: BetId) {
+        let mut bets = self.user_bets.get(owner).await.ok().flatten().unwrap_or_default();
         bets.push(bet_id);
-        self.user_bets.insert(&owner.to_string(), bets).expect("Failed to add user bet");
+        self.user_bets.insert(owner, bets).expect("Failed to add user bet");
     }
     
     /// Add a bet to market's bet list.
@@ -103,6 +314,109 @@ impl LivePredictState {
         self.market_bets.insert(&market_id, bets).expect("Failed to add market bet");
     }
     
+    /// Add a parlay to user's parlay list.
+    pub async fn add_user_parlay(&mut self, owner: &AccountOwner, parlay_id: ParlayId) {
+        let mut parlays = self.user_parlays.get(owner).await.ok().flatten().unwrap_or_default();
+        parlays.push(parlay_id);
+        self.user_parlays.insert(owner, parlays).expect("Failed to add user parlay");
+    }
+    
+    /// Get the total an owner has staked on a market.
+    pub async fn get_owner_stake(&self, market_id: MarketId, owner: &AccountOwner) -> Amount {
+        self.owner_stakes.get(&(market_id, *owner)).await.ok().flatten().unwrap_or(0)
+    }
+    
+    /// Add to the total an owner has staked on a market.
+    pub async fn add_owner_stake(
+        &mut self,
+        market_id: MarketId,
+        owner: &AccountOwner,
+        amount: Amount,
+    ) {
+        let total = self.get_owner_stake(market_id, owner).await + amount;
+        self.owner_stakes
+            .insert(&(market_id, *owner), total)
+            .expect("Failed to update owner stake");
+    }
+    
+    /// Take a cashed-out or refunded stake off the total an owner has staked on a market.
+    pub async fn remove_owner_stake(
+        &mut self,
+        market_id: MarketId,
+        owner: &AccountOwner,
+        amount: Amount,
+    ) {
+        let total = self.get_owner_stake(market_id, owner).await.saturating_sub(amount);
+        self.owner_stakes
+            .insert(&(market_id, *owner), total)
+            .expect("Failed to update owner stake");
+    }
+    
+    /// Stake limits applying to a market, falling back to the global limits.
+    pub fn market_limits(&self, market: &Market) -> StakeLimits {
+        let global = self.stake_limits.get();
+        StakeLimits {
+            max_stake: market.limits.max_stake.or(global.max_stake),
+            max_owner_stake: market.limits.max_owner_stake.or(global.max_owner_stake),
+            max_pool: market.limits.max_pool.or(global.max_pool),
+        }
+    }
+    
+    /// Get the markets conditional on a market.
+    pub async fn get_dependents(&self, market_id: MarketId) -> Vec<MarketId> {
+        self.market_dependents.get(&market_id).await.ok().flatten().unwrap_or_default()
+    }
+    
+    /// Add a conditional market to its parent's dependents.
+    pub async fn add_dependent(&mut self, parent_id: MarketId, market_id: MarketId) {
+        let mut dependents = self.get_dependents(parent_id).await;
+        dependents.push(market_id);
+        self.market_dependents.insert(&parent_id, dependents).expect("Failed to add dependent");
+    }
+    
+    /// Get the roles granted to an owner.
+    pub async fn get_roles(&self, owner: &AccountOwner) -> Vec<Role> {
+        self.roles.get(owner).await.ok().flatten().unwrap_or_default()
+    }
+    
+    /// Check whether an owner has been granted a role.
+    pub async fn has_role(&self, owner: &AccountOwner, role: Role) -> bool {
+        self.get_roles(owner).await.contains(&role)
+    }
+    
+    /// Grant a role to an owner. Returns `false` if the role was already granted.
+    pub async fn grant_role(&mut self, owner: &AccountOwner, role: Role) -> bool {
+        let mut roles = self.get_roles(owner).await;
+        if roles.contains(&role) {
+            return false;
+        }
+        roles.push(role);
+        self.roles.insert(owner, roles).expect("Failed to grant role");
+        true
+    }
+    
+    /// Revoke a role from an owner. Returns `false` if the role was not granted.
+    pub async fn revoke_role(&mut self, owner: &AccountOwner, role: Role) -> bool {
+        let mut roles = self.get_roles(owner).await;
+        if !roles.contains(&role) {
+            return false;
+        }
+        roles.retain(|&r| r != role);
+        if roles.is_empty() {
+            self.roles.remove(owner).expect("Failed to revoke role");
+        } else {
+            self.roles.insert(owner, roles).expect("Failed to revoke role");
+        }
+        true
+    }
+    
+    /// Record a settled bet split from a bet by a partial cash-out.
+    pub async fn add_bet_split(&mut self, bet_id: BetId, split_bet_id: BetId) {
+        let mut splits = self.bet_splits.get(&bet_id).await.ok().flatten().unwrap_or_default();
+        splits.push(split_bet_id);
+        self.bet_splits.insert(&bet_id, splits).expect("Failed to add bet split");
+    }
+    
     /// Add market to active markets list.
     pub async fn add_active_market(&mut self, market_id: MarketId) {
         let mut markets = self.active_markets.get().clone();
@@ -117,22 +431,641 @@ impl LivePredictState {
         self.active_markets.set(markets);
     }
     
-    /// Calculate odds for an option based on current pool distribution.
+    /// Calculate odds for an option based on current pool distribution, within the
+    /// market's odds floor and cap.
     /// Returns odds scaled by 1000 (e.g., 1500 = 1.5x).
-    pub fn calculate_odds(total_pool: Amount, option_pool: Amount) -> u32 {
+    pub fn calculate_odds(config: &OddsConfig, total_pool: Amount, option_pool: Amount) -> u32 {
         if option_pool == 0 {
-            return 2000; // Default 2x odds for empty pool
+            return config.default_odds; // Default odds for empty pool
         }
         
         // Odds = total_pool / option_pool, scaled by 1000
-        let odds = (total_pool as u64 * 1000) / (option_pool as u64);
-        odds.min(10000) as u32 // Cap at 10x
+        total_pool
+            .checked_mul(1000)
+            .map_or(Amount::MAX, |scaled| scaled / option_pool)
+            .clamp(config.floor.into(), config.cap.into()) as u32
+    }
+    
+    /// Outstanding shares of the live options of an LMSR market, and the position of
+    /// `option_id` among them. Voided options drop out of the market maker's book.
+    pub fn lmsr_book(market: &Market, option_id: u8) -> Option<(Vec<Amount>, usize)> {
+        let live = market.options.iter().filter(|o| !o.voided);
+        let position = live.clone().position(|o| o.id == option_id)?;
+        Some((live.map(|o| o.liability).collect(), position))
+    }
+    
+    /// Current odds offered on a market option (scaled by 1000), whatever the
+    /// market's pricing. Returns `None` if the option has no odds yet or was voided.
+    pub fn current_odds(market: &Market, option_id: u8) -> Option<u32> {
+        let option = market.options.get(option_id as usize).filter(|o| !o.voided)?;
+        match market.pricing {
+            PricingMode::Parimutuel => {
+                let total_pool: Amount = market.options.iter().map(|o| o.pool).sum();
+                Some(Self::calculate_odds(&market.odds_config, total_pool, option.pool))
+            }
+            PricingMode::FixedOdds => Some(option.odds).filter(|&odds| odds > 0),
+            PricingMode::Lmsr => {
+                let (outstanding, position) = Self::lmsr_book(market, option_id)?;
+                let price = *lmsr::prices(&outstanding, market.liquidity)?.get(position)?;
+                let odds = (1000 * lmsr::PRICE_SCALE as u64).checked_div(price as u64)?;
+                Some(odds.min(u32::MAX as u64) as u32)
+            }
+        }
+    }
+    
+    /// Outcome of a condition on a market's option so far, or `None` if the option
+    /// doesn't exist.
+    pub fn condition_status(market: &Market, option_id: u8) -> Option<LegStatus> {
+        market.options.get(option_id as usize)?;
+        Some(Self::leg_status(market, option_id))
+    }
+    
+    /// Outcome of a parlay leg on a market.
+    pub fn leg_status(market: &Market, option_id: u8) -> LegStatus {
+        if market.options[option_id as usize].voided {
+            return LegStatus::Void;
+        }
+        match market.status {
+            MarketStatus::Resolved
+                if market
+                    .winners
+                    .iter()
+                    .any(|winner| winner.option_id == option_id && winner.weight > 0) =>
+            {
+                LegStatus::Won
+            }
+            MarketStatus::Resolved => LegStatus::Lost,
+            MarketStatus::Cancelled | MarketStatus::Pushed => LegStatus::Void,
+            _ => LegStatus::Pending,
+        }
+    }
+    
+    /// Outcome of a parlay from the outcomes of its legs. A parlay is lost as soon as
+    /// any leg loses, and void if every leg is void.
+    pub fn parlay_status(legs: &[LegStatus]) -> LegStatus {
+        if legs.contains(&LegStatus::Lost) {
+            LegStatus::Lost
+        } else if legs.contains(&LegStatus::Pending) {
+            LegStatus::Pending
+        } else if legs.iter().all(|&status| status == LegStatus::Void) {
+            LegStatus::Void
+        } else {
+            LegStatus::Won
+        }
+    }
+    
+    /// Combined odds of the legs of a parlay that won (scaled by 1000), given the
+    /// payout weight of each leg. Void legs drop out and dead-heat legs count at their
+    /// weight.
+    pub fn settled_parlay_odds(parlay: &Parlay, weights: &[u32]) -> Amount {
+        parlay
+            .legs
+            .iter()
+            .zip(weights)
+            .filter(|(_, &weight)| weight > 0)
+            .fold(1000, |odds, (leg, &weight)| {
+                odds * leg.odds as Amount * weight as Amount / 10_000_000
+            })
+    }
+    
+    /// Calculate the protocol fee on an amount.
+    pub fn calculate_fee(amount: Amount, fee_rate_bps: u32) -> Amount {
+        amount * fee_rate_bps as Amount / 10000
+    }
+    
+    /// Calculate the gross payout of a winning fixed-odds bet.
+    pub fn calculate_fixed_payout(amount: Amount, odds: u32) -> Amount {
+        amount * odds as Amount / 1000
+    }
+    
+    /// Share of a full win paid on each option of a resolved market, in basis points.
+    pub fn payout_weights(market: &Market) -> Vec<u32> {
+        let mut weights = vec![0; market.options.len()];
+        for winner in &market.winners {
+            weights[winner.option_id as usize] = winner.weight;
+        }
+        weights
+    }
+    
+    /// Split of a scalar market's payouts between Long and Short for a result, in basis
+    /// points, according to where the result landed between the bounds. Computed in
+    /// `i128` so that bounds spanning most of the `i64` range don't overflow.
+    pub fn scalar_weights(market: &Market, result: i64) -> (u32, u32) {
+        let (lower, upper) = (market.bounds[0] as i128, market.bounds[1] as i128);
+        let long = ((result as i128).clamp(lower, upper) - lower) * 10000 / (upper - lower);
+        (long as u32, 10000 - long as u32)
+    }
+    
+    /// Relative share of a resolved parimutuel market's payout pool going to the bettors
+    /// on each option. Scalar markets split the pool between the sides that were bet on
+    /// by weight, other markets share it between winning stakes scaled by their weight.
+    fn pool_shares(market: &Market) -> Vec<Amount> {
+        market
+            .options
+            .iter()
+            .zip(Self::payout_weights(market))
+            .map(|(option, weight)| match market.kind {
+                _ if option.pool == 0 => 0,
+                MarketKind::Scalar => weight as Amount,
+                _ => option.pool * weight as Amount,
+            })
+            .collect()
+    }
+    
+    /// Whether nobody bet on a winning option of a resolved parimutuel market, in which
+    /// case every stake is refunded.
+    pub fn is_refunded(market: &Market) -> bool {
+        market.pricing == PricingMode::Parimutuel
+            && Self::pool_shares(market).iter().all(|&share| share == 0)
+    }
+    
+    /// Whether bets on an option of a resolved market lost their stake, the option
+    /// paying nothing and the market not being refunded.
+    pub fn is_losing_option(market: &Market, option_id: u8) -> bool {
+        Self::payout_weights(market)[option_id as usize] == 0 && !Self::is_refunded(market)
+    }
+    
+    /// Portion of a resolved parimutuel market's payout pool shared by the bettors on an
+    /// option.
+    pub fn option_payout_pool(market: &Market, option_id: u8) -> Amount {
+        let shares = Self::pool_shares(market);
+        let total: Amount = shares.iter().sum();
+        if total == 0 {
+            return 0;
+        }
+        market.payout_pool * shares[option_id as usize] / total
+    }
+    
+    /// Amount owed to the creator's seed on a resolved parimutuel market. Seed on the
+    /// winning options shares the payout pool like a bet, and the whole seed is
+    /// returned if the market is refunded.
+    pub fn seed_payout(market: &Market) -> Amount {
+        if Self::is_refunded(market) {
+            return market.seed.iter().sum();
+        }
+        market
+            .seed
+            .iter()
+            .zip(&market.options)
+            .map(|(&seed, option)| {
+                Self::calculate_parimutuel_payout(
+                    seed,
+                    option.pool,
+                    Self::option_payout_pool(market, option.id),
+                )
+            })
+            .sum()
+    }
+    
+    /// Settle a resolved market's pools, expecting its winners to be set.
+    /// Sets the payout pool reserved for winners and returns the protocol fee taken
+    /// from the pool. Stakes withheld by parimutuel cash-outs go to the winners. For
+    /// parimutuel markets where nobody backed a winning option stakes are refunded
+    /// and only the withheld stakes go to the protocol. Fixed-odds and LMSR markets
+    /// take their fee on each payout instead.
+    pub fn settle_pools(market: &mut Market, fee_rate_bps: u32) -> Amount {
+        let total_pool: Amount = market.options.iter().map(|o| o.pool).sum();
+        match market.pricing {
+            PricingMode::Parimutuel if Self::is_refunded(market) => {
+                market.payout_pool = total_pool;
+                market.retained
+            }
+            PricingMode::Parimutuel => {
+                let fee = Self::calculate_fee(total_pool + market.retained, fee_rate_bps);
+                market.payout_pool = total_pool + market.retained - fee;
+                fee
+            }
+            PricingMode::FixedOdds | PricingMode::Lmsr => {
+                let weights = Self::payout_weights(market);
+                market.payout_pool = market
+                    .options
+                    .iter()
+                    .zip(weights)
+                    .map(|(option, weight)| option.liability * weight as Amount / 10000)
+                    .sum();
+                0
+            }
+        }
+    }
+    
+    /// Value a bet for cash-out against the market's current pricing, before fees.
+    /// Parimutuel bets are valued at their stake scaled by how the odds moved since
+    /// the bet, capped at the stake so the pool stays solvent. Fixed-odds bets are
+    /// valued at their potential payout at the current odds, and LMSR shares are
+    /// sold back to the market maker.
+    pub fn cash_out_value(market: &Market, bet: &Bet) -> Option<Amount> {
+        let option = market.options.get(bet.option_id as usize)?;
+        match market.pricing {
+            PricingMode::Parimutuel => {
+                let total_pool: Amount = market.options.iter().map(|o| o.pool).sum();
+                let current_odds =
+                    Self::calculate_odds(&market.odds_config, total_pool, option.pool);
+                let value = bet.amount * bet.odds as Amount / current_odds as Amount;
+                Some(value.min(bet.amount))
+            }
+            PricingMode::FixedOdds => {
+                if option.odds == 0 {
+                    return None;
+                }
+                Some(bet.shares * 1000 / option.odds as Amount)
+            }
+            PricingMode::Lmsr => {
+                let (outstanding, position) = Self::lmsr_book(market, bet.option_id)?;
+                lmsr::sale_proceeds(&outstanding, market.liquidity, position, bet.shares)
+            }
+        }
+    }
+    
+    /// Amount the house must reserve for a market: the largest option liability not
+    /// covered by the stakes already in the market.
+    fn required_reserve(market: &Market) -> Amount {
+        let total_stakes: Amount = market.options.iter().map(|o| o.pool).sum();
+        let worst_case = market.options.iter().map(|o| o.liability).max().unwrap_or(0);
+        worst_case.saturating_sub(total_stakes)
+    }
+    
+    /// Record the house's profit or loss on stakes taken and payouts made.
+    async fn record_house_result(&mut self, currency: &Currency, stakes: Amount, payouts: Amount) {
+        let (profit, loss) = self.get_house_result(currency).await;
+        let result = if stakes >= payouts {
+            self.house_profit.insert(currency, profit + stakes - payouts)
+        } else {
+            self.house_loss.insert(currency, loss + payouts - stakes)
+        };
+        result.expect("Failed to record house result");
+    }
+    
+    /// Record a new liability on an option of a house-backed market, reserving enough
+    /// house liquidity to cover the market's worst-case payout. The new stake is assumed
+    /// to be already added to the option pool. Returns `false` if the house can't cover it.
+    pub async fn reserve_liability(
+        &mut self,
+        market: &mut Market,
+        option_id: u8,
+        liability: Amount,
+    ) -> bool {
+        market.options[option_id as usize].liability += liability;
+        let required = Self::required_reserve(market);
+        let available = self.get_house_liquidity(&market.currency).await + market.house_reserve;
+        if required > available {
+            market.options[option_id as usize].liability -= liability;
+            return false;
+        }
+        
+        self.set_house_liquidity(&market.currency, available - required);
+        market.house_reserve = required;
+        true
+    }
+    
+    /// Cash out a bet on a house-backed market: remove its stake and shares from the
+    /// option, pay `value` from the house and re-reserve liquidity for the remaining
+    /// worst case. Returns `false` if the house can't cover it.
+    pub async fn cash_out_house(
+        &mut self,
+        market: &mut Market,
+        option_id: u8,
+        stake: Amount,
+        shares: Amount,
+        value: Amount,
+    ) -> bool {
+        let liquidity = self.get_house_liquidity(&market.currency).await;
+        let Some(available) = (liquidity + market.house_reserve + stake).checked_sub(value) else {
+            return false;
+        };
+        
+        let option = &mut market.options[option_id as usize];
+        option.pool -= stake;
+        option.liability -= shares;
+        let required = Self::required_reserve(market);
+        if required > available {
+            let option = &mut market.options[option_id as usize];
+            option.pool += stake;
+            option.liability += shares;
+            return false;
+        }
+        
+        self.set_house_liquidity(&market.currency, available - required);
+        market.house_reserve = required;
+        self.record_house_result(&market.currency, stake, value).await;
+        true
+    }
+    
+    /// Return a settled house-backed market's unused funds to the house and record its
+    /// profit or loss. Expects the payout pool to have been set by `settle_pools`,
+    /// which the reserve and stakes cover as long as the winner weights add up to at
+    /// most one full win.
+    pub async fn settle_house(&mut self, market: &mut Market) {
+        let total_stakes: Amount = market.options.iter().map(|o| o.pool).sum();
+        let leftover = market.house_reserve + total_stakes - market.payout_pool;
+        let liquidity = self.get_house_liquidity(&market.currency).await;
+        self.set_house_liquidity(&market.currency, liquidity + leftover);
+        market.house_reserve = 0;
+        self.record_house_result(&market.currency, total_stakes, market.payout_pool).await;
+    }
+    
+    /// Reserve house liquidity for a parlay. Returns `false` if the house can't cover it.
+    pub async fn reserve_parlay(&mut self, currency: &Currency, reserve: Amount) -> bool {
+        let available = self.get_house_liquidity(currency).await;
+        if reserve > available {
+            return false;
+        }
+        self.set_house_liquidity(currency, available - reserve);
+        true
+    }
+    
+    /// Pay out a settled parlay's gross `payout` from its stake and reserve, returning
+    /// the rest to the house and recording its profit or loss.
+    pub async fn settle_parlay_house(&mut self, parlay: &Parlay, payout: Amount) {
+        let leftover = parlay.house_reserve + parlay.amount - payout;
+        let liquidity = self.get_house_liquidity(&parlay.currency).await;
+        self.set_house_liquidity(&parlay.currency, liquidity + leftover);
+        self.record_house_result(&parlay.currency, parlay.amount, payout).await;
+    }
+    
+    /// Remove a voided option's stakes and liability from a house-backed market, its
+    /// stakes being refunded, and re-reserve liquidity for the remaining worst case.
+    /// Returns `false` if the house can't cover it.
+    pub async fn void_house_option(&mut self, market: &mut Market, option_id: u8) -> bool {
+        let option = &mut market.options[option_id as usize];
+        let (pool, liability) = (option.pool, option.liability);
+        option.pool = 0;
+        option.liability = 0;
+        let required = Self::required_reserve(market);
+        let available = self.get_house_liquidity(&market.currency).await + market.house_reserve;
+        if required > available {
+            let option = &mut market.options[option_id as usize];
+            option.pool = pool;
+            option.liability = liability;
+            return false;
+        }
+        
+        self.set_house_liquidity(&market.currency, available - required);
+        market.house_reserve = required;
+        true
     }
     
-    /// Calculate potential payout for a bet.
-    pub fn calculate_payout(amount: Amount, odds: u32, fee_rate_bps: u32) -> Amount {
-        let gross_payout = (amount as u64 * odds as u64) / 1000;
-        let fee = (gross_payout * fee_rate_bps as u64) / 10000;
-        (gross_payout - fee) as Amount
+    /// Release a cancelled house-backed market's reserve back to the house.
+    pub async fn release_house_reserve(&mut self, market: &mut Market) {
+        let liquidity = self.get_house_liquidity(&market.currency).await;
+        self.set_house_liquidity(&market.currency, liquidity + market.house_reserve);
+        market.house_reserve = 0;
+    }
+    
+    /// Calculate a winning bet's share of the payout pool, proportional to its stake.
+    pub fn calculate_parimutuel_payout(
+        amount: Amount,
+        winning_pool: Amount,
+        payout_pool: Amount,
+    ) -> Amount {
+        if winning_pool == 0 {
+            return 0;
+        }
+        amount * payout_pool / winning_pool
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use linera_sdk::{
+        linera_base_types::AccountOwner,
+        views::{KeyValueStore, View, ViewStorageContext},
+    };
+    use live_predict::{
+        Amount, Bet, Market, MarketKind, MarketStatus, OddsConfig, PricingMode, Timestamp,
+        WinningOption,
+    };
+
+    use super::LivePredictState;
+    use crate::test_support::test_market;
+
+    /// Fee rate of the tests, 1%.
+    const FEE_RATE_BPS: u32 = 100;
+
+    /// Empty state kept in memory.
+    async fn create_state() -> LivePredictState {
+        let store = KeyValueStore::mock().to_mut();
+        LivePredictState::load(ViewStorageContext::new_unsafe(store, Vec::new(), ()))
+            .await
+            .expect("Failed to load state")
+    }
+
+    /// Resolve a market to winning options and their weights.
+    fn resolve(market: &mut Market, winners: &[(u8, u32)]) {
+        market.status = MarketStatus::Resolved;
+        market.winners = winners
+            .iter()
+            .map(|&(option_id, weight)| WinningOption { option_id, weight })
+            .collect();
+    }
+
+    /// An unsettled bet on an option.
+    fn bet(option_id: u8, amount: Amount, odds: u32, shares: Amount) -> Bet {
+        Bet {
+            id: 1,
+            owner: AccountOwner::Address20([2; 20]),
+            market_id: 1,
+            option_id,
+            amount,
+            odds,
+            shares,
+            placed_at: Timestamp::from(0),
+            settled: false,
+            cashed_out: false,
+            split_from: None,
+            payout: None,
+        }
+    }
+
+    /// A scalar market between two bounds.
+    fn scalar_market(lower: i64, upper: i64) -> Market {
+        let mut market = test_market(MarketKind::Scalar, PricingMode::Parimutuel, &[0, 0]);
+        market.bounds = vec![lower, upper];
+        market
+    }
+
+    #[test]
+    fn scalar_weights_split_by_position_between_bounds() {
+        let market = scalar_market(0, 10_000);
+        assert_eq!(LivePredictState::scalar_weights(&market, -5_000), (0, 10000));
+        assert_eq!(LivePredictState::scalar_weights(&market, 2_500), (2500, 7500));
+        assert_eq!(LivePredictState::scalar_weights(&market, 10_000), (10000, 0));
+        assert_eq!(LivePredictState::scalar_weights(&market, 20_000), (10000, 0));
+    }
+
+    #[test]
+    fn scalar_weights_handle_the_widest_bounds() {
+        let market = scalar_market(i64::MIN, i64::MAX);
+        assert_eq!(LivePredictState::scalar_weights(&market, i64::MIN), (0, 10000));
+        assert_eq!(LivePredictState::scalar_weights(&market, 0), (5000, 5000));
+        assert_eq!(LivePredictState::scalar_weights(&market, i64::MAX), (10000, 0));
+    }
+
+    #[test]
+    fn odds_hold_for_pools_beyond_u64() {
+        let config = OddsConfig::default();
+        let big = u64::MAX as Amount * 4;
+        // Pools past u64::MAX keep their full value
+        assert_eq!(LivePredictState::calculate_odds(&config, big, big / 2), 2000);
+        assert_eq!(LivePredictState::calculate_odds(&config, big, big), 1000);
+        assert_eq!(LivePredictState::calculate_odds(&config, Amount::MAX, 1), 10000);
+        assert_eq!(LivePredictState::calculate_odds(&config, big, 0), 2000);
+    }
+
+    #[test]
+    fn settling_a_parimutuel_market_takes_the_fee_from_the_pool() {
+        let mut market =
+            test_market(MarketKind::Categorical, PricingMode::Parimutuel, &[300, 700]);
+        market.retained = 50;
+        resolve(&mut market, &[(0, 10000)]);
+
+        let fee = LivePredictState::settle_pools(&mut market, FEE_RATE_BPS);
+        assert_eq!(fee, 10);
+        assert_eq!(market.payout_pool, 1040);
+        assert_eq!(LivePredictState::option_payout_pool(&market, 0), 1040);
+        assert_eq!(LivePredictState::option_payout_pool(&market, 1), 0);
+        assert!(LivePredictState::is_losing_option(&market, 1));
+    }
+
+    #[test]
+    fn dead_heat_splits_the_payout_pool_by_stake_and_weight() {
+        let mut market =
+            test_market(MarketKind::Categorical, PricingMode::Parimutuel, &[100, 300, 600]);
+        resolve(&mut market, &[(0, 5000), (1, 5000)]);
+
+        LivePredictState::settle_pools(&mut market, FEE_RATE_BPS);
+        assert_eq!(market.payout_pool, 990);
+        assert_eq!(LivePredictState::option_payout_pool(&market, 0), 247);
+        assert_eq!(LivePredictState::option_payout_pool(&market, 1), 742);
+        assert_eq!(LivePredictState::option_payout_pool(&market, 2), 0);
+        assert!(!LivePredictState::is_losing_option(&market, 0));
+        assert!(LivePredictState::is_losing_option(&market, 2));
+    }
+
+    #[test]
+    fn scalar_pools_split_between_the_sides_bet_on() {
+        let mut market = scalar_market(0, 10_000);
+        market.options[0].pool = 100;
+        market.options[1].pool = 900;
+        resolve(&mut market, &[(0, 2500), (1, 7500)]);
+        LivePredictState::settle_pools(&mut market, FEE_RATE_BPS);
+        assert_eq!(LivePredictState::option_payout_pool(&market, 0), 247);
+        assert_eq!(LivePredictState::option_payout_pool(&market, 1), 742);
+
+        // Nobody bet on Long, so Short takes the whole pool
+        market.options[0].pool = 0;
+        LivePredictState::settle_pools(&mut market, FEE_RATE_BPS);
+        assert_eq!(LivePredictState::option_payout_pool(&market, 0), 0);
+        assert_eq!(LivePredictState::option_payout_pool(&market, 1), market.payout_pool);
+    }
+
+    #[test]
+    fn unbacked_winner_refunds_stakes_and_seed() {
+        let mut market =
+            test_market(MarketKind::Categorical, PricingMode::Parimutuel, &[0, 500]);
+        market.seed = vec![0, 100];
+        market.retained = 20;
+        resolve(&mut market, &[(0, 10000)]);
+
+        assert!(LivePredictState::is_refunded(&market));
+        assert!(!LivePredictState::is_losing_option(&market, 1));
+        assert_eq!(LivePredictState::settle_pools(&mut market, FEE_RATE_BPS), 20);
+        assert_eq!(market.payout_pool, 500);
+        assert_eq!(LivePredictState::seed_payout(&market), 100);
+    }
+
+    #[test]
+    fn parimutuel_payouts_and_seed_never_exceed_the_payout_pool() {
+        // Stakes of the bets on each option, the seed, the winners and retained stakes
+        let cases = [
+            (vec![vec![7, 11, 13], vec![17]], vec![3, 5], vec![(0, 10000)], 0),
+            (vec![vec![333], vec![1, 1], vec![999]], vec![1, 2, 3], vec![(1, 10000)], 7),
+            (
+                vec![vec![101], vec![203], vec![307]],
+                vec![0, 0, 0],
+                vec![(0, 3334), (1, 3333), (2, 3333)],
+                0,
+            ),
+            (vec![vec![1000], vec![1], vec![1]], vec![10, 0, 0], vec![(0, 10000), (1, 10000)], 3),
+        ];
+        for (stakes, seed, winners, retained) in cases {
+            let pools: Vec<Amount> = stakes
+                .iter()
+                .zip(&seed)
+                .map(|(bets, seed)| bets.iter().sum::<Amount>() + seed)
+                .collect();
+            let mut market =
+                test_market(MarketKind::Categorical, PricingMode::Parimutuel, &pools);
+            market.seed = seed;
+            market.retained = retained;
+            resolve(&mut market, &winners);
+            LivePredictState::settle_pools(&mut market, FEE_RATE_BPS);
+
+            let bet_payouts: Amount = stakes
+                .iter()
+                .zip(&market.options)
+                .filter(|(_, option)| !LivePredictState::is_losing_option(&market, option.id))
+                .flat_map(|(bets, option)| {
+                    let option_pool = LivePredictState::option_payout_pool(&market, option.id);
+                    bets.iter().map(move |&amount| {
+                        LivePredictState::calculate_parimutuel_payout(
+                            amount,
+                            option.pool,
+                            option_pool,
+                        )
+                    })
+                })
+                .sum();
+            let paid = bet_payouts + LivePredictState::seed_payout(&market);
+            assert!(paid <= market.payout_pool, "paid {paid} of {}", market.payout_pool);
+            // Only rounding dust is left over
+            assert!(market.payout_pool - paid <= 10, "paid {paid} of {}", market.payout_pool);
+        }
+    }
+
+    #[test]
+    fn parimutuel_cash_out_follows_the_odds_up_to_the_stake() {
+        let bet = bet(0, 100, 2000, 0);
+        for (pools, value) in [([200, 800], 40), ([400, 400], 100), ([800, 200], 100)] {
+            let market = test_market(MarketKind::Categorical, PricingMode::Parimutuel, &pools);
+            assert_eq!(LivePredictState::cash_out_value(&market, &bet), Some(value));
+        }
+    }
+
+    #[test]
+    fn fixed_odds_cash_out_is_the_shares_at_the_current_odds() {
+        let bet = bet(0, 100, 3000, 300);
+        let mut market =
+            test_market(MarketKind::Categorical, PricingMode::FixedOdds, &[100, 0]);
+        assert_eq!(LivePredictState::cash_out_value(&market, &bet), None);
+        market.options[0].odds = 2500;
+        assert_eq!(LivePredictState::cash_out_value(&market, &bet), Some(120));
+    }
+
+    #[tokio::test]
+    async fn settling_the_house_returns_what_winners_dont_take() {
+        let currency = "USDC".to_string();
+        for (winners, liquidity, result) in [
+            (vec![(1, 10000)], 900, (0, 100)),
+            (vec![(0, 5000), (1, 5000)], 950, (0, 50)),
+            (vec![], 1300, (300, 0)),
+        ] {
+            let mut state = create_state().await;
+            state.set_house_liquidity(&currency, 1000);
+            let mut market =
+                test_market(MarketKind::Categorical, PricingMode::FixedOdds, &[0, 0]);
+            // 100 at 3x on option 0, then 200 at 2x on option 1
+            market.options[0].pool = 100;
+            assert!(state.reserve_liability(&mut market, 0, 300).await);
+            market.options[1].pool = 200;
+            assert!(state.reserve_liability(&mut market, 1, 400).await);
+            assert_eq!(market.house_reserve, 100);
+
+            resolve(&mut market, &winners);
+            assert_eq!(LivePredictState::settle_pools(&mut market, FEE_RATE_BPS), 0);
+            state.settle_house(&mut market).await;
+            assert_eq!(market.house_reserve, 0);
+            assert_eq!(state.get_house_liquidity(&currency).await, liquidity);
+            assert_eq!(state.get_house_result(&currency).await, result);
+        }
     }
 }
@@ -0,0 +1,52 @@
+// Copyright (c) Live Play Predictor
+// SPDX-License-Identifier: Apache-2.0
+
+//! Fixtures shared by the tests of the contract and the service.
+
+use linera_sdk::linera_base_types::AccountOwner;
+use live_predict::{
+    Amount, Market, MarketKind, MarketOption, MarketStatus, PricingMode, StakeLimits, Timestamp,
+};
+
+/// An open market of a kind and pricing with one option per pool.
+pub fn test_market(kind: MarketKind, pricing: PricingMode, pools: &[Amount]) -> Market {
+    Market {
+        id: 1,
+        match_id: "match".into(),
+        market_type: "test".into(),
+        title: "Test".into(),
+        kind,
+        line: None,
+        teams: Vec::new(),
+        bounds: Vec::new(),
+        creator: AccountOwner::Address20([1; 20]),
+        currency: "USDC".into(),
+        odds_config: Default::default(),
+        limits: StakeLimits::default(),
+        condition: None,
+        pricing,
+        liquidity: 0,
+        options: pools
+            .iter()
+            .enumerate()
+            .map(|(id, &pool)| MarketOption {
+                id: id as u8,
+                label: format!("Option {id}"),
+                pool,
+                odds: 0,
+                liability: 0,
+                voided: false,
+            })
+            .collect(),
+        status: MarketStatus::Open,
+        created_at: Timestamp::from(0),
+        locks_at: Timestamp::from(1_000_000),
+        winners: Vec::new(),
+        result: None,
+        payout_pool: 0,
+        house_reserve: 0,
+        retained: 0,
+        seed: Vec::new(),
+        seed_payout: None,
+    }
+}